@@ -5,9 +5,124 @@ use std::{
     sync::{Mutex, OnceLock},
 };
 
+use serde::{Deserialize, Serialize};
+
 const PROJECT_CONFIG_PATH: &str = "projects/config.json";
 const TOKEN_PATH: &str = "projects/.ghtoken";
 
+/// How changes that only move scripts and comments around the workspace are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutChanges {
+    /// Mentioned in commit messages like any other change
+    Show,
+    /// Committed without being mentioned or marking sprites as changed
+    #[default]
+    Silent,
+    /// Reverted before committing so they never reach the repository
+    Ignore,
+}
+
+/// Which kinds of state that change while a project runs are reset to their last committed
+/// value, so repositories only record authored changes
+///
+/// Only variable values and the user agent are reset by default, since nothing but running or
+/// saving the project changes them. Positions, directions, costumes and monitor positions are
+/// also set by hand in the editor, so resetting them has to be turned on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NormalizeRules {
+    /// Variable values, list contents and monitor values
+    pub variables: bool,
+    /// Sprite x and y positions
+    pub positions: bool,
+    /// Sprite directions
+    pub directions: bool,
+    /// Current costumes and the current backdrop
+    pub costumes: bool,
+    /// Monitor positions
+    pub monitors: bool,
+    /// The user agent of the browser that saved the project
+    pub agent: bool,
+}
+
+impl Default for NormalizeRules {
+    fn default() -> Self {
+        NormalizeRules {
+            variables: true,
+            positions: false,
+            directions: false,
+            costumes: false,
+            monitors: false,
+            agent: true,
+        }
+    }
+}
+
+/// Built-in styles for commit messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitPreset {
+    /// `Sprite1: +3 blocks, add a.png` with changed scripts in the body
+    #[default]
+    Default,
+    /// The default style prefixed with the kind of change, like `feat: ` or `fix: `, following
+    /// Conventional Commits
+    Conventional,
+    /// `Update Sprite1, Stage` with every change on its own line in the body
+    Verbose,
+}
+
+/// How commit messages are written
+///
+/// The subject and body come from a preset unless they're given here. Templates can use the
+/// placeholders `{changes}`, `{sprites}`, `{blocks}`, `{assets}`, `{variables}`, `{scripts}`
+/// and `{comments}`, which are lists joined by `, ` in the subject and by lines in the body,
+/// and `{type}`, the Conventional Commits type of the changes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommitTemplate {
+    pub preset: CommitPreset,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+impl CommitTemplate {
+    /// Template for the first line of the commit message
+    pub fn subject(&self) -> &str {
+        if let Some(subject) = &self.subject {
+            return subject;
+        }
+        match self.preset {
+            CommitPreset::Default => "{changes}",
+            CommitPreset::Conventional => "{type}: {changes}",
+            CommitPreset::Verbose => "Update {sprites}",
+        }
+    }
+
+    /// Template for the rest of the commit message
+    pub fn body(&self) -> &str {
+        if let Some(body) = &self.body {
+            return body;
+        }
+        match self.preset {
+            CommitPreset::Default | CommitPreset::Conventional => "{scripts}\n{comments}",
+            CommitPreset::Verbose => "{changes}\n\n{scripts}\n{comments}",
+        }
+    }
+}
+
+/// Per-project options for how changes are detected and committed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectSettings {
+    pub layout_changes: LayoutChanges,
+    pub normalize: NormalizeRules,
+    pub commit_template: CommitTemplate,
+}
+
 /// Represents a loaded project path and the path to it
 #[derive(Debug)]
 pub struct ProjectConfig {
@@ -48,6 +163,17 @@ impl ProjectConfig {
             .unwrap()
             .to_string()
     }
+
+    /// Returns a project's settings, falling back to the defaults for anything not set
+    pub fn settings(&self, project_name: &str) -> ProjectSettings {
+        serde_json::from_value(self.projects[project_name]["settings"].clone()).unwrap_or_default()
+    }
+
+    /// Replace a project's settings and save the config
+    pub fn set_settings(&mut self, project_name: &str, settings: &ProjectSettings) {
+        self.projects[project_name]["settings"] = serde_json::to_value(settings).unwrap();
+        self.save();
+    }
 }
 
 /// Singleton project configuration
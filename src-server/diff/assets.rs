@@ -0,0 +1,183 @@
+use super::structs::{AssetMove, AssetMoveType, Diff};
+use crate::sb3::{Asset, Target};
+
+/// A costume or sound as seen when looking for moved assets
+struct Entry<'a> {
+    /// Name of the sprite in the newer project, used for comparisons
+    sprite: String,
+    /// Name of the sprite in its own project
+    label: String,
+    kind: &'static str,
+    name: &'a str,
+    ext: &'a str,
+    path: String,
+}
+
+fn entries(target: &Target, sprite: String) -> Vec<Entry<'_>> {
+    let costumes = target.costumes.iter().map(|c| ("costume", c as &dyn Asset));
+    let sounds = target.sounds.iter().map(|s| ("sound", s as &dyn Asset));
+    costumes
+        .chain(sounds)
+        .map(|(kind, asset)| Entry {
+            sprite: sprite.clone(),
+            label: target.display_name(),
+            kind,
+            name: asset.name(),
+            ext: asset.data_format(),
+            path: asset.file_name(),
+        })
+        .collect()
+}
+
+impl Diff {
+    /// Return costumes and sounds that were renamed, moved between sprites or copied
+    ///
+    /// Assets are matched by their content hash, so an asset that was renamed and edited at the
+    /// same time is still reported as a removal and an addition. Sprites that were added or
+    /// removed are ignored, so duplicating a sprite doesn't count as copying all of its assets
+    pub fn asset_moves(&self, new: &Diff) -> Vec<AssetMove> {
+        let mut old_assets = vec![];
+        let mut new_assets = vec![];
+        for pair in self.matched_targets(new) {
+            let (Some(old), Some(new)) = pair else {
+                continue;
+            };
+            old_assets.extend(entries(old, new.display_name()));
+            new_assets.extend(entries(new, new.display_name()));
+        }
+
+        let same =
+            |a: &Entry, b: &Entry| a.sprite == b.sprite && a.kind == b.kind && a.name == b.name;
+        let removed: Vec<&Entry> = old_assets
+            .iter()
+            .filter(|old| !new_assets.iter().any(|new| same(old, new)))
+            .collect();
+        let added = new_assets
+            .iter()
+            .filter(|new| !old_assets.iter().any(|old| same(old, new)));
+
+        // prefer assets from the same sprite so renames aren't mistaken for moves
+        let find = |entry: &Entry, candidates: &mut dyn Iterator<Item = (usize, &Entry)>| {
+            let candidates: Vec<_> = candidates
+                .filter(|(_, c)| c.kind == entry.kind && c.path == entry.path)
+                .collect();
+            candidates
+                .iter()
+                .find(|(_, c)| c.sprite == entry.sprite)
+                .or(candidates.first())
+                .map(|(i, _)| *i)
+        };
+
+        let mut used = vec![false; removed.len()];
+        let mut moves = vec![];
+        for asset in added {
+            let moved = find(
+                asset,
+                &mut removed
+                    .iter()
+                    .copied()
+                    .enumerate()
+                    .filter(|(i, _)| !used[*i]),
+            );
+            let (from, copy) = match moved {
+                Some(i) => {
+                    used[i] = true;
+                    (removed[i], false)
+                }
+                None => match find(asset, &mut old_assets.iter().enumerate()) {
+                    Some(i) => (&old_assets[i], true),
+                    None => continue,
+                },
+            };
+            let action = match (from.sprite == asset.sprite, copy) {
+                (true, false) => AssetMoveType::Rename,
+                (true, true) => AssetMoveType::Duplicate,
+                (false, false) => AssetMoveType::Move,
+                (false, true) => AssetMoveType::Copy,
+            };
+            moves.push(AssetMove {
+                sprite: asset.label.clone(),
+                from_sprite: from.label.clone(),
+                kind: asset.kind,
+                name: asset.name.to_string(),
+                from_name: from.name.to_string(),
+                path: asset.path.clone(),
+                ext: asset.ext.to_string(),
+                action,
+            });
+        }
+        moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A project with sprites holding costumes given as `(name, assetId)`
+    fn project(sprites: &[(&str, &[(&str, &str)])]) -> Diff {
+        let sprites: Vec<_> = sprites
+            .iter()
+            .map(|(name, costumes)| {
+                let costumes: Vec<_> = costumes
+                    .iter()
+                    .map(|(name, id)| {
+                        format!(r#"{{"assetId":"{id}","name":"{name}","dataFormat":"svg"}}"#)
+                    })
+                    .collect();
+                format!(
+                    r#"{{"isStage":false,"name":"{name}","costumes":[{}]}}"#,
+                    costumes.join(",")
+                )
+            })
+            .collect();
+        let json = format!(
+            r#"{{"targets":[{{"isStage":true,"name":"Stage"}},{}]}}"#,
+            sprites.join(",")
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    fn actions(old: &Diff, new: &Diff) -> Vec<AssetMoveType> {
+        old.asset_moves(new).iter().map(|m| m.action).collect()
+    }
+
+    #[test]
+    fn renamed_costume() {
+        let old = project(&[("Cat", &[("costume1", "cat")])]);
+        let new = project(&[("Cat", &[("walking", "cat")])]);
+        assert_eq!(actions(&old, &new), vec![AssetMoveType::Rename]);
+    }
+
+    #[test]
+    fn moved_and_copied_costumes() {
+        let old = project(&[("Cat", &[("costume1", "cat")]), ("Dog", &[("dog", "dog")])]);
+
+        let new = project(&[
+            ("Cat", &[]),
+            ("Dog", &[("dog", "dog"), ("costume1", "cat")]),
+        ]);
+        assert_eq!(actions(&old, &new), vec![AssetMoveType::Move]);
+
+        let new = project(&[
+            ("Cat", &[("costume1", "cat")]),
+            ("Dog", &[("dog", "dog"), ("costume1", "cat")]),
+        ]);
+        assert_eq!(actions(&old, &new), vec![AssetMoveType::Copy]);
+    }
+
+    #[test]
+    fn added_and_removed_sprites_are_ignored() {
+        let old = project(&[("Cat", &[("costume1", "cat")])]);
+        let new = project(&[
+            ("Cat", &[("costume1", "cat")]),
+            ("Cat2", &[("costume1", "cat")]),
+        ]);
+        assert!(actions(&old, &new).is_empty());
+
+        let old = project(&[("Cat", &[("costume1", "cat")]), ("Dog", &[("dog", "dog")])]);
+        let new = project(&[("Cat", &[("costume1", "cat"), ("dog", "dog")])]);
+        assert!(actions(&old, &new).is_empty());
+    }
+}
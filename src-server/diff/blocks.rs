@@ -0,0 +1,243 @@
+use super::structs::{BlockDiff, Diff};
+use crate::sb3::{Block, Input, InputValue, Primitive, Target};
+
+/// A primitive without its workspace position
+fn without_position(primitive: &Primitive) -> Primitive {
+    match primitive.clone() {
+        Primitive::Variable { name, id, rest, .. } => Primitive::Variable {
+            name,
+            id,
+            position: None,
+            rest,
+        },
+        Primitive::List { name, id, rest, .. } => Primitive::List {
+            name,
+            id,
+            position: None,
+            rest,
+        },
+        primitive => primitive,
+    }
+}
+
+/// What's placed in an input, where blocks are compared by ID since their content is compared
+/// on its own, so swapping the block in an input changes the block holding it
+fn contents(input: &Input) -> [Option<InputValue>; 2] {
+    let content = |value: &Option<InputValue>| match value {
+        Some(InputValue::Primitive(primitive)) => {
+            Some(InputValue::Primitive(without_position(primitive)))
+        }
+        value => value.clone(),
+    };
+    [content(&input.value), content(&input.shadow)]
+}
+
+/// Whether a block looks the same in both projects, regardless of where it is
+fn same_content(old: &Block, new: &Block) -> bool {
+    match (old, new) {
+        (Block::Normal(old), Block::Normal(new)) => {
+            old.opcode == new.opcode
+                && old.fields == new.fields
+                && old.mutation == new.mutation
+                && old.inputs.len() == new.inputs.len()
+                && old.inputs.iter().all(|(name, input)| {
+                    new.inputs
+                        .get(name)
+                        .is_some_and(|new| contents(input) == contents(new))
+                })
+        }
+        (Block::Primitive(old), Block::Primitive(new)) => {
+            without_position(old) == without_position(new)
+        }
+        _ => false,
+    }
+}
+
+/// Whether a block was dragged, either around the workspace or to another parent
+///
+/// A block whose parent was added or removed only moved because a block was inserted above it
+/// or deleted, so it doesn't count
+fn was_moved(old_target: &Target, new_target: &Target, old: &Block, new: &Block) -> bool {
+    match (old, new) {
+        (Block::Normal(old), Block::Normal(new)) if old.parent != new.parent => {
+            let old_parent_kept = old
+                .parent
+                .as_ref()
+                .is_none_or(|id| new_target.blocks.contains_key(id));
+            let new_parent_existed = new
+                .parent
+                .as_ref()
+                .is_none_or(|id| old_target.blocks.contains_key(id));
+            old_parent_kept && new_parent_existed
+        }
+        (Block::Normal(old), Block::Normal(new)) => {
+            new.top_level && (old.x != new.x || old.y != new.y)
+        }
+        (Block::Primitive(old), Block::Primitive(new)) => {
+            old != new && without_position(old) == without_position(new)
+        }
+        _ => false,
+    }
+}
+
+/// The top-level block of the script a block is in
+fn script_of<'a>(target: &'a Target, mut id: &'a str) -> &'a str {
+    // a corrupt project could have a cycle of parents, so stop after visiting every block
+    for _ in 0..target.blocks.len() {
+        match target.blocks.get(id) {
+            Some(Block::Normal(block)) => match &block.parent {
+                Some(parent) => id = parent,
+                None => break,
+            },
+            _ => break,
+        }
+    }
+    id
+}
+
+/// The closest block that isn't a shadow, so changing a dropdown highlights the block it's in
+fn visible_block<'a>(target: &'a Target, mut id: &'a str) -> &'a str {
+    while let Some(Block::Normal(block)) = target.blocks.get(id) {
+        match (&block.parent, block.shadow) {
+            (Some(parent), true) => id = parent,
+            _ => break,
+        }
+    }
+    id
+}
+
+impl Diff {
+    /// Compare the blocks of a sprite by their IDs, where the stage is named `Stage (stage)`
+    pub fn block_diff(&self, new: &Diff, sprite_name: &str) -> BlockDiff {
+        let pair = self
+            .matched_targets(new)
+            .into_iter()
+            .find(|pair| match pair {
+                (_, Some(target)) | (Some(target), None) => {
+                    target.display_name() == sprite_name
+                        || (sprite_name == "Stage (stage)" && target.is_stage)
+                }
+                (None, None) => false,
+            });
+        let Some((old, new)) = pair else {
+            return BlockDiff::default();
+        };
+
+        let mut diff = BlockDiff::default();
+        match (old, new) {
+            (Some(old), Some(new)) => {
+                for (id, block) in &new.blocks {
+                    match old.blocks.get(id) {
+                        None => diff.added.push(id.clone()),
+                        Some(old_block) => {
+                            if !same_content(old_block, block) {
+                                let id = visible_block(new, id).to_string();
+                                if !diff.modified.contains(&id) {
+                                    diff.modified.push(id);
+                                }
+                            }
+                            if was_moved(old, new, old_block, block) {
+                                let script = script_of(new, id).to_string();
+                                if !diff.moved.contains(&script) {
+                                    diff.moved.push(script);
+                                }
+                            }
+                        }
+                    }
+                }
+                diff.removed = old
+                    .blocks
+                    .keys()
+                    .filter(|id| !new.blocks.contains_key(*id))
+                    .cloned()
+                    .collect();
+            }
+            (None, Some(new)) => diff.added = new.blocks.keys().cloned().collect(),
+            (Some(old), None) => diff.removed = old.blocks.keys().cloned().collect(),
+            (None, None) => {}
+        }
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A project with a single sprite holding the given blocks
+    fn project(blocks: &str) -> Diff {
+        let json =
+            format!(r#"{{"targets":[{{"isStage":false,"name":"Sprite1","blocks":{blocks}}}]}}"#);
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    const SCRIPTS: &str = r#"{
+        "flag":{"opcode":"event_whenflagclicked","next":"move","topLevel":true,"x":0,"y":0},
+        "move":{"opcode":"motion_movesteps","next":"turn","parent":"flag","inputs":{"STEPS":[1,[4,"10"]]}},
+        "turn":{"opcode":"motion_turnright","parent":"move","inputs":{"DEGREES":[1,[4,"15"]]}},
+        "click":{"opcode":"event_whenthisspriteclicked","topLevel":true,"x":0,"y":200}
+    }"#;
+
+    #[test]
+    fn typed_value_changes_modify_the_block() {
+        let new = SCRIPTS.replace(r#"[4,"10"]"#, r#"[4,"20"]"#);
+        let diff = project(SCRIPTS).block_diff(&project(&new), "Sprite1");
+        assert_eq!(diff.modified, vec!["move"]);
+        assert!(diff.moved.is_empty());
+    }
+
+    #[test]
+    fn inserting_a_block_moves_nothing() {
+        let new = SCRIPTS
+            .replace(r#""next":"turn""#, r#""next":"wait""#)
+            .replace(r#""parent":"move","inputs":{"DEGREES""#, r#""parent":"wait","inputs":{"DEGREES""#)
+            .replace(
+                r#""click":"#,
+                r#""wait":{"opcode":"control_wait","next":"turn","parent":"move","inputs":{"DURATION":[1,[5,"1"]]}},"click":"#,
+            );
+        let diff = project(SCRIPTS).block_diff(&project(&new), "Sprite1");
+        assert_eq!(diff.added, vec!["wait"]);
+        assert!(diff.moved.is_empty());
+        assert!(diff.modified.is_empty());
+    }
+
+    #[test]
+    fn dragging_reports_scripts() {
+        // the turn block is dragged from under the flag script to under the click script
+        let new = SCRIPTS
+            .replace(r#""next":"turn","parent":"flag""#, r#""parent":"flag""#)
+            .replace(
+                r#""parent":"move","inputs":{"DEGREES""#,
+                r#""parent":"click","inputs":{"DEGREES""#,
+            )
+            .replace(
+                r#""opcode":"event_whenthisspriteclicked","topLevel""#,
+                r#""opcode":"event_whenthisspriteclicked","next":"turn","topLevel""#,
+            );
+        let diff = project(SCRIPTS).block_diff(&project(&new), "Sprite1");
+        assert_eq!(diff.moved, vec!["click"]);
+
+        let new = SCRIPTS.replace(r#""x":0,"y":200"#, r#""x":100,"y":200"#);
+        let diff = project(SCRIPTS).block_diff(&project(&new), "Sprite1");
+        assert_eq!(diff.moved, vec!["click"]);
+    }
+
+    #[test]
+    fn swapping_a_reporter_modifies_its_parent() {
+        let old = SCRIPTS
+            .replace(r#""STEPS":[1,[4,"10"]]"#, r#""STEPS":[3,"x",[4,"10"]]"#)
+            .replace(
+                r#""click":"#,
+                r#""x":{"opcode":"motion_xposition","parent":"move"},"click":"#,
+            );
+        let new = old.replace(r#"[3,"x","#, r#"[3,"y","#).replace(
+            r#""x":{"opcode":"motion_xposition""#,
+            r#""y":{"opcode":"motion_yposition""#,
+        );
+        let diff = project(&old).block_diff(&project(&new), "Sprite1");
+        assert_eq!(diff.added, vec!["y"]);
+        assert_eq!(diff.removed, vec!["x"]);
+        assert_eq!(diff.modified, vec!["move"]);
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+
+use super::scripts::script_label;
+use super::structs::{CommentChange, CommentChangeType, Diff};
+use crate::sb3::{Block, Target};
+
+/// Readable name of the script a block belongs to
+fn script_of(target: &Target, block_id: &str) -> Option<String> {
+    let mut visited = HashSet::new();
+    let mut id = block_id;
+    loop {
+        let block = target.blocks.get(id)?;
+        match block {
+            Block::Normal(normal) if !block.is_top_level() && visited.insert(id) => {
+                id = normal.parent.as_deref()?;
+            }
+            _ => return Some(script_label(target, block)),
+        }
+    }
+}
+
+impl Diff {
+    /// Return workspace comments that were added, removed, edited or attached to another script
+    pub fn comment_changes(&self, new: &Diff) -> Vec<CommentChange> {
+        let mut changes = vec![];
+
+        for pair in self.matched_targets(new) {
+            let sprite = match pair {
+                (_, Some(target)) | (Some(target), None) => target.display_name(),
+                (None, None) => continue,
+            };
+            let mut change = |id: &str, text: &str, action| {
+                changes.push(CommentChange {
+                    sprite: sprite.clone(),
+                    id: id.to_string(),
+                    text: text.to_string(),
+                    action,
+                })
+            };
+
+            if let Some(new) = pair.1 {
+                for (id, comment) in &new.comments {
+                    let Some((old, old_comment)) =
+                        pair.0.and_then(|old| Some((old, old.comments.get(id)?)))
+                    else {
+                        change(id, &comment.text, CommentChangeType::Add);
+                        continue;
+                    };
+
+                    if old_comment.text != comment.text {
+                        change(
+                            id,
+                            &comment.text,
+                            CommentChangeType::Edit {
+                                from: old_comment.text.clone(),
+                            },
+                        );
+                    }
+
+                    let attached_to = |target: &Target, block_id: &Option<String>| {
+                        block_id.as_deref().and_then(|b| script_of(target, b))
+                    };
+                    if old_comment.block_id != comment.block_id {
+                        let (from, to) = (
+                            attached_to(old, &old_comment.block_id),
+                            attached_to(new, &comment.block_id),
+                        );
+                        // moving a comment to another block in the same script isn't interesting
+                        if from != to {
+                            change(id, &comment.text, CommentChangeType::Reattach { from, to });
+                        }
+                    }
+                }
+            }
+            if let Some(old) = pair.0 {
+                for (id, comment) in &old.comments {
+                    if !pair.1.is_some_and(|new| new.comments.contains_key(id)) {
+                        change(id, &comment.text, CommentChangeType::Remove);
+                    }
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A project with two scripts and a sprite holding the given comments
+    fn project(comments: &str) -> Diff {
+        let json = format!(
+            r#"{{"targets":[{{"isStage":true,"name":"Stage"}},{{"isStage":false,"name":"Sprite1","blocks":{{
+                "flag":{{"opcode":"event_whenflagclicked","next":"move","topLevel":true,"x":0,"y":0}},
+                "move":{{"opcode":"motion_movesteps","parent":"flag","inputs":{{"STEPS":[1,[4,"10"]]}}}},
+                "click":{{"opcode":"event_whenthisspriteclicked","topLevel":true,"x":0,"y":200}}
+            }},"comments":{comments}}}]}}"#
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    fn comment(block: &str, text: &str) -> String {
+        format!(
+            r#"{{"note":{{"blockId":{block},"x":0,"y":0,"width":200,"height":200,"text":"{text}"}}}}"#
+        )
+    }
+
+    fn changes(old: &Diff, new: &Diff) -> Vec<String> {
+        old.comment_changes(new)
+            .iter()
+            .map(|change| change.format())
+            .collect()
+    }
+
+    #[test]
+    fn added_edited_and_removed_comments() {
+        let empty = project("{}");
+        let note = project(&comment("null", "todo"));
+        assert_eq!(changes(&empty, &note), vec![r#"add comment "todo""#]);
+        assert_eq!(changes(&note, &empty), vec![r#"remove comment "todo""#]);
+
+        let edited = project(&comment("null", "done"));
+        assert_eq!(
+            changes(&note, &edited),
+            vec![r#"edit comment "todo" -> "done""#]
+        );
+    }
+
+    #[test]
+    fn comments_are_attached_to_scripts() {
+        let loose = project(&comment("null", "todo"));
+        let on_move = project(&comment(r#""move""#, "todo"));
+        let on_flag = project(&comment(r#""flag""#, "todo"));
+        let on_click = project(&comment(r#""click""#, "todo"));
+
+        assert_eq!(
+            changes(&loose, &on_move),
+            vec![r#"attach comment "todo" to when green flag clicked"#]
+        );
+        // another block in the same script
+        assert!(changes(&on_move, &on_flag).is_empty());
+        assert_eq!(
+            changes(&on_flag, &on_click),
+            vec![r#"move comment "todo" from when green flag clicked to when this sprite clicked"#]
+        );
+        assert_eq!(
+            changes(&on_click, &loose),
+            vec![r#"detach comment "todo" from when this sprite clicked"#]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use indexmap::IndexMap;
+
+use super::structs::{DataChange, DataChangeType, Diff};
+
+/// Compare two maps of IDs to names, detecting renames through the unchanged ID
+fn compare_names(
+    sprite: &str,
+    kind: &'static str,
+    old: IndexMap<&String, &String>,
+    new: IndexMap<&String, &String>,
+) -> Vec<DataChange> {
+    let mut changes = vec![];
+    for (id, name) in &new {
+        let action = match old.get(id) {
+            None => DataChangeType::Add,
+            Some(old_name) if old_name != name => DataChangeType::Rename {
+                from: old_name.to_string(),
+            },
+            Some(_) => continue,
+        };
+        changes.push(DataChange {
+            sprite: sprite.into(),
+            kind,
+            name: name.to_string(),
+            action,
+        });
+    }
+    for (id, name) in &old {
+        if !new.contains_key(id) {
+            changes.push(DataChange {
+                sprite: sprite.into(),
+                kind,
+                name: name.to_string(),
+                action: DataChangeType::Remove,
+            });
+        }
+    }
+    changes
+}
+
+impl Diff {
+    /// Return variables, lists and broadcasts that were added, removed or renamed in each sprite
+    ///
+    /// Global variables and lists, and all broadcasts, belong to the stage
+    pub fn data_changes(&self, new: &Diff) -> Vec<DataChange> {
+        let mut changes = vec![];
+        // data of added and removed sprites goes along with the sprite
+        for (old_target, new_target) in self.matched_targets(new) {
+            let (Some(old_target), Some(new_target)) = (old_target, new_target) else {
+                continue;
+            };
+            let sprite = new_target.display_name();
+
+            changes.extend(compare_names(
+                &sprite,
+                "variable",
+                old_target
+                    .variables
+                    .iter()
+                    .map(|(id, v)| (id, &v.name))
+                    .collect(),
+                new_target
+                    .variables
+                    .iter()
+                    .map(|(id, v)| (id, &v.name))
+                    .collect(),
+            ));
+            changes.extend(compare_names(
+                &sprite,
+                "list",
+                old_target
+                    .lists
+                    .iter()
+                    .map(|(id, l)| (id, &l.name))
+                    .collect(),
+                new_target
+                    .lists
+                    .iter()
+                    .map(|(id, l)| (id, &l.name))
+                    .collect(),
+            ));
+            changes.extend(compare_names(
+                &sprite,
+                "broadcast",
+                old_target.broadcasts.iter().collect(),
+                new_target.broadcasts.iter().collect(),
+            ));
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A project with the given global variables and broadcasts, and a sprite with the given
+    /// local lists, each written as `"id":...` JSON entries
+    fn project(variables: &str, broadcasts: &str, lists: &str) -> Diff {
+        let json = format!(
+            r#"{{"targets":[{{"isStage":true,"name":"Stage","variables":{{{variables}}},"broadcasts":{{{broadcasts}}}}},{{"isStage":false,"name":"Sprite1","lists":{{{lists}}}}}]}}"#
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    fn changes(old: &Diff, new: &Diff) -> Vec<String> {
+        old.data_changes(new)
+            .iter()
+            .map(|change| format!("{}: {}", change.sprite, change.format()))
+            .collect()
+    }
+
+    #[test]
+    fn renames_are_found_by_id() {
+        let old = project(
+            r#""a":["score",0]"#,
+            r#""b":"start""#,
+            r#""c":["items",[]]"#,
+        );
+        let new = project(r#""a":["points",3]"#, r#""b":"go""#, r#""c":["things",[]]"#);
+        assert_eq!(
+            changes(&old, &new),
+            vec![
+                "Stage (stage): rename variable score -> points",
+                "Stage (stage): rename broadcast start -> go",
+                "Sprite1: rename list items -> things",
+            ]
+        );
+    }
+
+    #[test]
+    fn added_and_removed_data() {
+        let old = project(r#""a":["score",0]"#, "", "");
+        let new = project(r#""b":["lives",3]"#, r#""c":"start""#, "");
+        assert_eq!(
+            changes(&old, &new),
+            vec![
+                "Stage (stage): add variable lives",
+                "Stage (stage): remove variable score",
+                "Stage (stage): add broadcast start",
+            ]
+        );
+    }
+
+    #[test]
+    fn values_are_not_changes() {
+        let old = project(r#""a":["score",0]"#, "", r#""c":["items",[]]"#);
+        let new = project(r#""a":["score",10]"#, "", r#""c":["items",["x"]]"#);
+        assert!(changes(&old, &new).is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use super::scratchblocks::template;
+use super::structs::{Diff, ExtensionChange, ExtensionChangeType};
+use crate::sb3::ProjectData;
+
+/// Readable name of an extension by its ID, or the ID itself for unknown and custom extensions
+pub fn extension_name(id: &str) -> &str {
+    match id {
+        "pen" => "Pen",
+        "music" => "Music",
+        "videoSensing" => "Video Sensing",
+        "text2speech" => "Text to Speech",
+        "translate" => "Translate",
+        "makeymakey" => "Makey Makey",
+        "microbit" => "micro:bit",
+        "ev3" => "LEGO MINDSTORMS EV3",
+        "boost" => "LEGO BOOST",
+        "wedo2" => "LEGO Education WeDo 2.0",
+        "gdxfor" => "Go Direct Force & Acceleration",
+        id => id,
+    }
+}
+
+/// Whether an opcode belongs to an extension rather than one of the core block categories
+pub fn is_extension_opcode(opcode: &str) -> bool {
+    let Some((category, _)) = opcode.split_once('_') else {
+        return false;
+    };
+    !matches!(
+        category,
+        "motion"
+            | "looks"
+            | "sound"
+            | "event"
+            | "control"
+            | "sensing"
+            | "operator"
+            | "data"
+            | "procedures"
+            | "argument"
+    )
+}
+
+/// Readable name of a block from a built-in extension, like `set pen color to (Pen)`, taken
+/// from its scratchblocks template without the inputs
+pub fn opcode_name(opcode: &str) -> Option<String> {
+    if !is_extension_opcode(opcode) {
+        return None;
+    }
+    let (extension, _) = opcode.split_once('_')?;
+    let (template, _) = template(opcode)?;
+    let name: Vec<_> = template
+        .split_whitespace()
+        .filter(|word| !word.starts_with('{'))
+        .collect();
+    Some(format!(
+        "{} ({})",
+        name.join(" "),
+        extension_name(extension)
+    ))
+}
+
+impl Diff {
+    /// Return extensions that were added to or removed from the project, or whose URL changed
+    pub fn extension_changes(&self, new: &Diff) -> Vec<ExtensionChange> {
+        let (old, new) = (&self.data, &new.data);
+        let url = |project: &ProjectData, id: &str| {
+            project
+                .extension_urls
+                .as_ref()
+                .and_then(|urls| urls.get(id))
+                .cloned()
+        };
+        let change = |id: &str, url, action| ExtensionChange {
+            id: id.to_string(),
+            name: extension_name(id).to_string(),
+            url,
+            action,
+        };
+
+        let mut changes = vec![];
+        for id in new.extensions() {
+            if !old.extensions().contains(id) {
+                changes.push(change(id, url(new, id), ExtensionChangeType::Add));
+            } else if url(old, id) != url(new, id) {
+                changes.push(change(id, url(new, id), ExtensionChangeType::Update));
+            }
+        }
+        for id in old.extensions() {
+            if !new.extensions().contains(id) {
+                changes.push(change(id, url(old, id), ExtensionChangeType::Remove));
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_blocks_are_named_by_their_template() {
+        assert_eq!(
+            opcode_name("pen_setPenColorToColor").as_deref(),
+            Some("set pen color to (Pen)")
+        );
+        assert_eq!(
+            opcode_name("music_playDrumForBeats").as_deref(),
+            Some("play drum for beats (Music)")
+        );
+        assert_eq!(opcode_name("motion_movesteps"), None);
+        assert_eq!(opcode_name("faceSensing_whenFaceDetected"), None);
+    }
+}
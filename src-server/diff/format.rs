@@ -0,0 +1,305 @@
+use std::collections::{HashMap, HashSet};
+
+use super::extensions::opcode_name;
+use super::walk::{prototype, render_proccode, signature, substacks, Walker};
+use crate::sb3::{Block, Input, InputValue, Mutation, NormalBlock, Primitive, Target};
+
+const INDENT: &str = "    ";
+
+/// Join formatted scripts into one text, sorted so that moving scripts around doesn't change it
+pub fn join_scripts(mut scripts: Vec<String>) -> String {
+    scripts.sort_by_key(|script| script.to_lowercase());
+
+    scripts
+        .into_iter()
+        .map(|script| script + "\n")
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Walks the block graph of a sprite to produce a stable text representation of its scripts
+///
+/// Every block is written on its own line with its inputs and fields sorted by name, reporters
+/// are nested in parentheses, and blocks inside C-blocks are indented. Block IDs never appear,
+/// so only changes a user could see affect the output
+pub struct ScriptFormatter<'a> {
+    walker: Walker<'a>,
+}
+
+impl<'a> ScriptFormatter<'a> {
+    pub fn new(target: &'a Target) -> Self {
+        ScriptFormatter {
+            walker: Walker::new(target),
+        }
+    }
+
+    /// Format every script, sorted so that moving scripts around doesn't change the output
+    pub fn format(self) -> String {
+        self.format_except(&HashSet::new())
+    }
+
+    /// Format every script except the ones starting at the given top-level blocks
+    pub fn format_except(self, skipped: &HashSet<&str>) -> String {
+        join_scripts(
+            self.scripts()
+                .into_iter()
+                .filter(|(id, _)| !skipped.contains(id))
+                .map(|(_, script)| script)
+                .collect(),
+        )
+    }
+
+    /// Format each script on its own, keyed by the ID of its top-level block
+    pub fn scripts(mut self) -> Vec<(&'a str, String)> {
+        let mut scripts = vec![];
+        for (id, block) in self.walker.scripts() {
+            let mut lines = vec![];
+            match block {
+                Block::Normal(_) => self.stack(id, 0, &mut lines),
+                Block::Primitive(primitive) => lines.push(ScriptFormatter::primitive(primitive)),
+            }
+            scripts.push((id, lines.join("\n")));
+        }
+        scripts
+    }
+
+    /// Format a stack of blocks starting at `id`, following `next`
+    fn stack(&mut self, id: &'a str, depth: usize, lines: &mut Vec<String>) {
+        let mut current = Some(id);
+        while let Some(block) = current.and_then(|id| self.walker.block(id)) {
+            lines.push(INDENT.repeat(depth) + &self.line(block));
+
+            for (name, input) in substacks(block) {
+                let Some(InputValue::Block(first)) = &input.value else {
+                    continue;
+                };
+                let start = lines.len();
+                self.stack(first, depth + 1, lines);
+                // mark where the else branch of an if/else (or any later C) begins, even when the
+                // first branch is empty
+                if name != "SUBSTACK" && lines.len() > start {
+                    let line = &mut lines[start];
+                    let indent = INDENT.repeat(depth + 1);
+                    *line = format!("{indent}{name}: {}", &line[indent.len()..]);
+                }
+            }
+
+            current = block.next.as_deref();
+        }
+    }
+
+    /// Format a block with its fields and non-stack inputs
+    fn line(&mut self, block: &'a NormalBlock) -> String {
+        match (block.opcode.as_str(), &block.mutation) {
+            ("procedures_definition", _) => {
+                if let Some(mutation) = prototype(self.walker.target, block) {
+                    let mut line = format!("define {}", signature(mutation));
+                    if mutation.warp() {
+                        line += " [run without screen refresh]";
+                    }
+                    return line;
+                }
+            }
+            ("procedures_call", Some(mutation)) => return self.call(block, mutation),
+            _ => {}
+        }
+
+        // extension blocks are named the way they read in the editor
+        let mut parts = vec![opcode_name(&block.opcode).unwrap_or(block.opcode.clone())];
+
+        if let Some(proccode) = block.mutation.as_ref().and_then(|m| m.proccode.as_ref()) {
+            parts.push(format!("<{proccode}>"));
+        }
+
+        let mut fields: Vec<_> = block.fields.iter().collect();
+        fields.sort_by_key(|(name, _)| name.as_str());
+        for (name, field) in fields {
+            parts.push(format!("{name}=[{}]", field.text()));
+        }
+
+        let mut inputs: Vec<_> = block
+            .inputs
+            .iter()
+            .filter(|(name, _)| !name.starts_with("SUBSTACK"))
+            .collect();
+        inputs.sort_by_key(|(name, _)| name.as_str());
+        for (name, input) in inputs {
+            parts.push(format!("{name}={}", self.input(input)));
+        }
+
+        parts.join(" ")
+    }
+
+    /// Format a custom block call by its proccode, with each input named after its argument,
+    /// like `call jump (height=[10])`
+    fn call(&mut self, block: &'a NormalBlock, mutation: &'a Mutation) -> String {
+        let proccode = mutation.proccode.as_deref().unwrap_or("");
+        let names: HashMap<String, String> = match self.walker.prototypes.get(proccode) {
+            Some(prototype) => prototype
+                .argument_ids()
+                .into_iter()
+                .zip(prototype.argument_names())
+                .collect(),
+            None => HashMap::new(),
+        };
+
+        let mut arguments = vec![];
+        for id in mutation.argument_ids() {
+            let value = match block.inputs.get(&id) {
+                Some(input) => self.input(input),
+                None => "[]".into(),
+            };
+            let name = names.get(&id).unwrap_or(&id);
+            arguments.push(format!("{name}={value}"));
+        }
+
+        format!("call {}", render_proccode(proccode, &arguments))
+    }
+
+    /// Format what's placed in an input
+    ///
+    /// The shadow obscured by a reporter is left out since it can't be seen in the editor
+    fn input(&mut self, input: &'a Input) -> String {
+        match &input.value {
+            None => "[]".into(),
+            Some(InputValue::Primitive(primitive)) => ScriptFormatter::primitive(primitive),
+            Some(InputValue::Block(id)) => {
+                let Some(block) = self.walker.block(id) else {
+                    return "[]".into();
+                };
+                // dropdown menus are shadow blocks holding a single field
+                if block.shadow && block.inputs.is_empty() && block.fields.len() == 1 {
+                    let field = block.fields.values().next().unwrap();
+                    return format!("[{}]", field.text());
+                }
+                format!("({})", self.line(block))
+            }
+        }
+    }
+
+    fn primitive(primitive: &Primitive) -> String {
+        match primitive {
+            Primitive::Broadcast { name, .. } => format!("(broadcast {name})"),
+            Primitive::Variable { name, .. } => format!("(variable {name})"),
+            Primitive::List { name, .. } => format!("(list {name})"),
+            primitive => format!("[{}]", primitive.text()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sprite holding the given blocks
+    fn sprite(blocks: &str) -> Target {
+        serde_json::from_str(&format!(
+            r#"{{"isStage":false,"name":"Sprite1","blocks":{blocks}}}"#
+        ))
+        .unwrap()
+    }
+
+    /// A green flag script running an if/else with the given branches
+    fn if_else(substack: Option<&str>, substack2: Option<&str>) -> Target {
+        let branch = |name: &str, id: Option<&str>| {
+            id.map(|id| format!(r#","{name}":[2,"{id}"]"#))
+                .unwrap_or_default()
+        };
+        sprite(&format!(
+            r#"{{
+                "flag":{{"opcode":"event_whenflagclicked","next":"if","topLevel":true,"x":0,"y":0}},
+                "if":{{"opcode":"control_if_else","parent":"flag","inputs":{{"CONDITION":[2,"touching"]{}{}}}}},
+                "touching":{{"opcode":"sensing_mousedown","parent":"if"}},
+                "move":{{"opcode":"motion_movesteps","parent":"if","inputs":{{"STEPS":[1,[4,"10"]]}}}},
+                "say":{{"opcode":"looks_say","parent":"if","inputs":{{"MESSAGE":[1,[10,"hi"]]}}}}
+            }}"#,
+            branch("SUBSTACK", substack),
+            branch("SUBSTACK2", substack2),
+        ))
+    }
+
+    #[test]
+    fn if_else_marks_else_branch() {
+        let target = if_else(Some("move"), Some("say"));
+        assert_eq!(
+            ScriptFormatter::new(&target).format(),
+            "event_whenflagclicked\n\
+             control_if_else CONDITION=(sensing_mousedown)\n    \
+             motion_movesteps STEPS=[10]\n    \
+             SUBSTACK2: looks_say MESSAGE=[hi]\n"
+        );
+    }
+
+    #[test]
+    fn if_else_with_only_else_branch() {
+        let target = if_else(None, Some("say"));
+        assert_eq!(
+            ScriptFormatter::new(&target).format(),
+            "event_whenflagclicked\n\
+             control_if_else CONDITION=(sensing_mousedown)\n    \
+             SUBSTACK2: looks_say MESSAGE=[hi]\n"
+        );
+    }
+
+    #[test]
+    fn if_else_with_only_if_branch() {
+        let target = if_else(Some("move"), None);
+        assert_eq!(
+            ScriptFormatter::new(&target).format(),
+            "event_whenflagclicked\n\
+             control_if_else CONDITION=(sensing_mousedown)\n    \
+             motion_movesteps STEPS=[10]\n"
+        );
+    }
+
+    #[test]
+    fn extension_blocks_are_named() {
+        let target = sprite(
+            r#"{
+                "flag":{"opcode":"event_whenflagclicked","next":"pen","topLevel":true,"x":0,"y":0},
+                "pen":{"opcode":"pen_setPenSizeTo","parent":"flag","inputs":{"SIZE":[1,[4,"5"]]}}
+            }"#,
+        );
+        assert_eq!(
+            ScriptFormatter::new(&target).format(),
+            "event_whenflagclicked\nset pen size to (Pen) SIZE=[5]\n"
+        );
+    }
+
+    #[test]
+    fn scripts_sorted_without_ids() {
+        let target = sprite(
+            r#"{
+                "b":{"opcode":"event_whenthisspriteclicked","topLevel":true,"x":0,"y":0},
+                "a":{"opcode":"event_whenflagclicked","topLevel":true,"x":0,"y":100},
+                "var":[12,"score","id",50,50]
+            }"#,
+        );
+        assert_eq!(
+            ScriptFormatter::new(&target).format(),
+            "(variable score)\n\nevent_whenflagclicked\n\nevent_whenthisspriteclicked\n"
+        );
+    }
+
+    #[test]
+    fn corrupt_blocks_are_skipped() {
+        // both scripts claim the same block, and the first one continues to a missing block
+        let target = sprite(
+            r#"{
+                "flag":{"opcode":"event_whenflagclicked","next":"shared","topLevel":true,"x":0,"y":0},
+                "click":{"opcode":"event_whenthisspriteclicked","next":"shared","topLevel":true,"x":0,"y":0},
+                "shared":{"opcode":"motion_movesteps","next":"gone","parent":"flag","inputs":{"STEPS":[3,"gone",[4,"10"]]}}
+            }"#,
+        );
+        assert_eq!(
+            ScriptFormatter::new(&target).scripts(),
+            vec![
+                (
+                    "flag",
+                    "event_whenflagclicked\nmotion_movesteps STEPS=[]".to_string()
+                ),
+                ("click", "event_whenthisspriteclicked".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use super::structs::Diff;
+use crate::sb3::{Block, Comment, Primitive, Target};
+
+/// Position of a top-level block on the workspace, if it has one
+fn position(block: &Block) -> Option<(String, String)> {
+    match block {
+        Block::Normal(block) if block.top_level => {
+            Some((block.x.as_ref()?.to_string(), block.y.as_ref()?.to_string()))
+        }
+        Block::Primitive(Primitive::Variable { position, .. })
+        | Block::Primitive(Primitive::List { position, .. }) => position
+            .as_ref()
+            .map(|(x, y)| (x.to_string(), y.to_string())),
+        _ => None,
+    }
+}
+
+/// Copy the position of a top-level block from an older version of it
+fn restore_position(block: &mut Block, old: &Block) {
+    match (block, old) {
+        (Block::Normal(block), Block::Normal(old)) if block.top_level && old.top_level => {
+            block.x = old.x.clone();
+            block.y = old.y.clone();
+        }
+        (
+            Block::Primitive(Primitive::Variable { position, .. }),
+            Block::Primitive(Primitive::Variable {
+                position: old_position,
+                ..
+            }),
+        )
+        | (
+            Block::Primitive(Primitive::List { position, .. }),
+            Block::Primitive(Primitive::List {
+                position: old_position,
+                ..
+            }),
+        ) => *position = old_position.clone(),
+        _ => {}
+    }
+}
+
+fn comment_layout(comment: &Comment) -> [Option<String>; 4] {
+    [&comment.x, &comment.y, &comment.width, &comment.height]
+        .map(|n| n.as_ref().map(|n| n.to_string()))
+}
+
+/// Whether any script or comment in both versions of a sprite was moved or resized
+fn layout_changed(old: &Target, new: &Target) -> bool {
+    let blocks_moved = new.blocks.iter().any(|(id, block)| {
+        old.blocks
+            .get(id)
+            .is_some_and(|old| position(old) != position(block))
+    });
+    let comments_moved = new.comments.iter().any(|(id, comment)| {
+        old.comments
+            .get(id)
+            .is_some_and(|old| comment_layout(old) != comment_layout(comment))
+    });
+    let reordered = !new
+        .blocks
+        .keys()
+        .filter(|id| old.blocks.contains_key(*id))
+        .eq(old.blocks.keys().filter(|id| new.blocks.contains_key(*id)));
+    blocks_moved || comments_moved || reordered
+}
+
+impl Diff {
+    /// Return sprites whose scripts or comments were moved around the workspace, which doesn't
+    /// change what a project does
+    pub fn layout_changes(&self, new: &Diff) -> Vec<String> {
+        self.matched_targets(new)
+            .into_iter()
+            .filter_map(|pair| match pair {
+                (Some(old), Some(new)) if layout_changed(old, new) => Some(new.display_name()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Put scripts, comments and monitors back where they were in an older project, keeping the
+    /// order blocks were saved in so cleaning up scripts doesn't show up in the repository
+    pub fn restore_layout(&mut self, old: &Diff) {
+        if let (Some(monitors), Some(old_monitors)) =
+            (self.data.monitors.as_mut(), old.data.monitors.as_ref())
+        {
+            for monitor in monitors {
+                if let Some(old) = old_monitors.iter().find(|m| m.id == monitor.id) {
+                    monitor.x = old.x.clone();
+                    monitor.y = old.y.clone();
+                    monitor.width = old.width.clone();
+                    monitor.height = old.height.clone();
+                }
+            }
+        }
+
+        for (i, j) in old.matched_indices(self) {
+            let (old, new) = (&old.data.targets[i], &mut self.data.targets[j]);
+
+            for (id, block) in new.blocks.iter_mut() {
+                if let Some(old) = old.blocks.get(id) {
+                    restore_position(block, old);
+                }
+            }
+            for (id, comment) in new.comments.iter_mut() {
+                if let Some(old) = old.comments.get(id) {
+                    comment.x = old.x.clone();
+                    comment.y = old.y.clone();
+                    comment.width = old.width.clone();
+                    comment.height = old.height.clone();
+                }
+            }
+
+            let order: HashMap<&String, usize> = old
+                .blocks
+                .keys()
+                .enumerate()
+                .map(|(i, id)| (id, i))
+                .collect();
+            new.blocks
+                .sort_by_cached_key(|id, _| order.get(id).copied().unwrap_or(usize::MAX));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A project with a sprite holding the given blocks and comments
+    fn project(blocks: &str, comments: &str) -> Diff {
+        let json = format!(
+            r#"{{"targets":[{{"isStage":true,"name":"Stage"}},{{"isStage":false,"name":"Sprite1","blocks":{blocks},"comments":{comments}}}]}}"#
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    const BLOCKS: &str = r#"{
+        "flag":{"opcode":"event_whenflagclicked","next":"move","topLevel":true,"x":0,"y":0},
+        "move":{"opcode":"motion_movesteps","parent":"flag","inputs":{"STEPS":[1,[4,"10"]]}},
+        "click":{"opcode":"event_whenthisspriteclicked","topLevel":true,"x":0,"y":200}
+    }"#;
+    const COMMENTS: &str =
+        r#"{"note":{"blockId":null,"x":300,"y":0,"width":200,"height":200,"text":"hi"}}"#;
+
+    #[test]
+    fn moved_scripts_and_comments_are_layout_changes() {
+        let old = project(BLOCKS, COMMENTS);
+        let moved = project(&BLOCKS.replace(r#""y":200"#, r#""y":400"#), COMMENTS);
+        assert_eq!(old.layout_changes(&moved), vec!["Sprite1"]);
+
+        let resized = project(
+            BLOCKS,
+            &COMMENTS.replace(r#""width":200"#, r#""width":100"#),
+        );
+        assert_eq!(old.layout_changes(&resized), vec!["Sprite1"]);
+    }
+
+    #[test]
+    fn edits_are_not_layout_changes() {
+        let old = project(BLOCKS, COMMENTS);
+        let edited = project(&BLOCKS.replace(r#"[4,"10"]"#, r#"[4,"20"]"#), COMMENTS);
+        assert!(old.layout_changes(&edited).is_empty());
+    }
+
+    #[test]
+    fn restoring_keeps_edits() {
+        let old = project(BLOCKS, COMMENTS);
+        let mut new = project(
+            &BLOCKS
+                .replace(r#""y":200"#, r#""y":400"#)
+                .replace(r#"[4,"10"]"#, r#"[4,"20"]"#),
+            &COMMENTS.replace(r#""x":300"#, r#""x":0"#),
+        );
+        new.restore_layout(&old);
+        assert!(old.layout_changes(&new).is_empty());
+        assert_eq!(
+            new.data,
+            project(&BLOCKS.replace(r#"[4,"10"]"#, r#"[4,"20"]"#), COMMENTS).data
+        );
+    }
+}
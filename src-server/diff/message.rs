@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use regex_static::{once_cell::sync::Lazy, Regex};
+
+use super::structs::{CommitParts, Diff};
+use super::vec_utils::group_items;
+use crate::config::ProjectSettings;
+
+static BLANK_LINES: Lazy<Regex> = regex_static::lazy_regex!(r"\n{3,}");
+
+/// Changes prefixed by their sprite, grouped by sprite when `grouped` is set
+fn lines(items: &[(String, String)], grouped: bool) -> Vec<String> {
+    if !grouped {
+        return items
+            .iter()
+            .map(|(sprite, change)| format!("{sprite}: {change}"))
+            .collect();
+    }
+    let mut groups: Vec<_> = group_items(items.to_vec()).into_iter().collect();
+    groups.sort();
+    groups
+        .iter()
+        .map(|(sprite, changes)| format!("{}: {}", sprite, changes.join(", ")))
+        .collect()
+}
+
+/// Fill in the placeholders of a template, joining lists with `, ` in the subject and with
+/// lines in the body
+///
+/// A subject with no changes to mention says `Update project` instead
+fn render(template: &str, parts: &CommitParts, subject: bool) -> String {
+    let separator = if subject { ", " } else { "\n" };
+    let mut sprites: Vec<&str> = vec![];
+    for (sprite, _) in &parts.changes {
+        let sprite = sprite.strip_suffix(" (stage)").unwrap_or(sprite);
+        if !sprites.contains(&sprite) {
+            sprites.push(sprite);
+        }
+    }
+    sprites.sort();
+
+    let (changes, sprites) = if parts.changes.is_empty() && subject {
+        // only changes that aren't mentioned, like moving scripts around
+        (vec!["Update project".into()], vec!["project".into()])
+    } else {
+        (
+            lines(&parts.changes, true),
+            sprites.iter().map(|s| s.to_string()).collect(),
+        )
+    };
+
+    let placeholders = [
+        ("{type}", vec![parts.change_type.to_string()]),
+        ("{changes}", changes),
+        ("{sprites}", sprites),
+        ("{blocks}", lines(&parts.blocks, false)),
+        ("{assets}", lines(&parts.assets, true)),
+        ("{variables}", lines(&parts.variables, true)),
+        ("{scripts}", lines(&parts.scripts, false)),
+        ("{comments}", lines(&parts.comments, false)),
+    ];
+    placeholders
+        .into_iter()
+        .fold(template.to_string(), |text, (placeholder, values)| {
+            text.replace(placeholder, &values.join(separator))
+        })
+}
+
+impl Diff {
+    /// Write the subject and body of a commit message for changes to a newer project
+    ///
+    /// The body is empty if the template's placeholders had nothing to show
+    pub fn commit_message(
+        &self,
+        cwd: &PathBuf,
+        new: &Diff,
+        settings: &ProjectSettings,
+    ) -> Result<(String, String)> {
+        let parts = self.commit_parts(cwd, new, settings)?;
+        let template = &settings.commit_template;
+
+        let subject = render(template.subject(), &parts, true);
+        // placeholders with nothing to show leave blank lines behind
+        let body = render(template.body(), &parts, false);
+        let body = BLANK_LINES.replace_all(body.trim(), "\n\n");
+
+        Ok((subject, body.into_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CommitPreset, CommitTemplate};
+
+    fn items(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(sprite, change)| (sprite.to_string(), change.to_string()))
+            .collect()
+    }
+
+    fn subject(preset: CommitPreset, parts: &CommitParts) -> String {
+        let template = CommitTemplate {
+            preset,
+            ..Default::default()
+        };
+        render(template.subject(), parts, true)
+    }
+
+    #[test]
+    fn changes_are_grouped_by_sorted_sprite() {
+        let parts = CommitParts {
+            change_type: "feat",
+            changes: items(&[
+                ("Sprite2", "+2 blocks"),
+                ("Sprite1", "add sprite"),
+                ("Sprite2", "add costume a.png"),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            subject(CommitPreset::Default, &parts),
+            "Sprite1: add sprite, Sprite2: +2 blocks, add costume a.png"
+        );
+        assert_eq!(
+            subject(CommitPreset::Conventional, &parts),
+            "feat: Sprite1: add sprite, Sprite2: +2 blocks, add costume a.png"
+        );
+        assert_eq!(
+            subject(CommitPreset::Verbose, &parts),
+            "Update Sprite1, Sprite2"
+        );
+    }
+
+    #[test]
+    fn nothing_to_mention_still_uses_the_template() {
+        let parts = CommitParts {
+            change_type: "chore",
+            ..Default::default()
+        };
+        assert_eq!(subject(CommitPreset::Default, &parts), "Update project");
+        assert_eq!(
+            subject(CommitPreset::Conventional, &parts),
+            "chore: Update project"
+        );
+        assert_eq!(subject(CommitPreset::Verbose, &parts), "Update project");
+
+        let template = CommitTemplate {
+            preset: CommitPreset::Verbose,
+            ..Default::default()
+        };
+        assert_eq!(render(template.body(), &parts, false).trim(), "");
+    }
+
+    #[test]
+    fn change_type_follows_the_changes() {
+        let project = |variable: &str, blocks: &str| {
+            let json = format!(
+                r#"{{"targets":[{{"isStage":true,"name":"Stage","variables":{{"id":["{variable}",0]}}}},{{"isStage":false,"name":"Sprite1","blocks":{blocks}}}]}}"#
+            );
+            Diff::new(crate::sb3::ProjectData::from_json(&json).unwrap())
+        };
+        let flag = r#"{"flag":{"opcode":"event_whenflagclicked","topLevel":true,"x":0,"y":0}}"#;
+        // scripts are diffed with git, which needs a repository to store them in
+        let cwd = crate::git::temp_repo("change-type");
+        let change_type = |old: &Diff, new: &Diff| {
+            old.commit_parts(&cwd, new, &ProjectSettings::default())
+                .unwrap()
+                .change_type
+        };
+
+        let old = project("score", "{}");
+        assert_eq!(change_type(&old, &project("points", "{}")), "refactor");
+        assert_eq!(change_type(&old, &project("score", flag)), "feat");
+        assert_eq!(change_type(&project("score", flag), &old), "fix");
+        assert_eq!(change_type(&old, &project("score", "{}")), "chore");
+        std::fs::remove_dir_all(&cwd).unwrap();
+    }
+}
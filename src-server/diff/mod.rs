@@ -1,35 +1,72 @@
+pub mod assets;
+pub mod blocks;
+pub mod comments;
+pub mod data;
+pub mod extensions;
+pub mod format;
+pub mod layout;
+pub mod message;
+pub mod monitors;
+pub mod normalize;
+pub mod procedures;
+pub mod properties;
+pub mod renames;
+pub mod scratchblocks;
+pub mod scripts;
+pub mod split;
 pub mod structs;
+pub mod targets;
 pub mod vec_utils;
+pub mod walk;
 
 use structs::*;
 
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::{
-    collections::{HashMap, HashSet},
-    vec,
-};
 
 use anyhow::Result;
-use itertools::EitherOrBoth::{Both, Left, Right};
-use itertools::Itertools;
-use regex_static::{once_cell::sync::Lazy, Regex};
-use serde_json::{Map, Value};
 
+use crate::config::{LayoutChanges, ProjectSettings};
 use crate::git;
+use crate::sb3::{Asset, ProjectData, Target};
+use format::{join_scripts, ScriptFormatter};
+use scripts::{changed_scripts, formatted_scripts};
 use vec_utils::{group_items, intersect_costumes};
 
-// removes ids from block statements to make diffs accurate
-static REMOVE_IDS: Lazy<Regex> = regex_static::lazy_regex!(r#"":\[(?:1|2|3),"\w""#);
+/// What changed between the settings of two versions of an asset, like
+/// `rotation center 48,50 -> 0,0`, or `None` if nothing did
+fn describe(old: &[(&str, String)], new: &[(&str, String)]) -> Option<String> {
+    let changes: Vec<_> = old
+        .iter()
+        .zip(new)
+        .filter(|((_, a), (_, b))| a != b)
+        .map(|((field, a), (_, b))| format!("{field} {a} -> {b}"))
+        .collect();
+    (!changes.is_empty()).then(|| changes.join("; "))
+}
+
+/// Diff of scripts that were all added or all removed, with every line marked by `sign`
+fn whole_file_diff(content: &str, sign: char) -> String {
+    content
+        .lines()
+        .map(|line| format!("{sign}{line}\n"))
+        .collect()
+}
 
 impl Diff {
     /// Construct a new diff from a project.json
     ///
     /// ```
-    /// let project = json!({"targets":[{"isStage":true,"name":"Stage","variables": ... "monitors":[],"extensions":[]}});
-    /// Diff::new(&project);
+    /// let project = ProjectData::from_json(r#"{"targets":[{"isStage":true,"name":"Stage","variables": ... "monitors":[],"extensions":[]}"#)?;
+    /// Diff::new(project);
     /// ```
-    pub fn new(data: &Value) -> Self {
-        Diff { data: data.clone() }
+    pub fn new(data: ProjectData) -> Self {
+        Diff { data }
+    }
+
+    /// Construct a new diff from the contents of a project.json, failing if it isn't a valid project
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(Diff::new(ProjectData::from_json(json)?))
     }
 
     /// Construct a new diff from a project.json located in a certain Git revision
@@ -40,21 +77,28 @@ impl Diff {
     /// Diff::from_revision(&pth, "HEAD~1:project.json");
     /// ```
     pub fn from_revision(pth: &PathBuf, commit: &str) -> Result<Self> {
-        let json = git::show_revision(pth, commit);
-        let data = serde_json::from_str::<serde_json::Value>(&json?)?;
-        Ok(Diff { data: data.clone() })
+        Diff::from_json(&git::show_revision(pth, commit)?)
+    }
+
+    /// Find a sprite by name, where the stage is named `Stage (stage)`
+    pub fn target(&self, sprite_name: &str) -> Option<&Target> {
+        self.data.targets.iter().find(|t| {
+            if sprite_name == "Stage (stage)" {
+                t.is_stage
+            } else {
+                t.name == sprite_name && !t.is_stage
+            }
+        })
     }
 
-    /// Attempt to return the MD5 extension of a costume item (project.json)
-    pub fn get_asset_path(costume: Value) -> String {
-        costume["md5ext"]
-            .as_str()
-            .map(|md5| md5.to_string())
-            .unwrap_or(format!(
-                "{}.{}",
-                costume["assetId"].as_str().unwrap(),
-                costume["dataFormat"].as_str().unwrap()
-            ))
+    /// Format the scripts of a sprite for diffing, where the stage is named `Stage (stage)`
+    ///
+    /// Returns an empty string if the sprite doesn't exist
+    pub fn scripts(&self, sprite_name: &str) -> String {
+        match self.target(sprite_name) {
+            Some(target) => Diff::format_blocks(target),
+            None => String::new(),
+        }
     }
 
     /// Return costumes that have changed between projects, but not added or removed
@@ -74,6 +118,18 @@ impl Diff {
         let they_match =
             |a: &AssetChange, b: &AssetChange| a.name == b.name && a.sprite == b.sprite;
 
+        // describe settings that changed alongside the file
+        let mut merged: Vec<AssetChange> = merged
+            .into_iter()
+            .map(|item| {
+                let new = added.iter().find(|x| they_match(x, &item));
+                AssetChange {
+                    description: new.and_then(|new| describe(&item.metadata, &new.metadata)),
+                    ..item
+                }
+            })
+            .collect();
+
         for item in &merged {
             if let Some(pos) = added.iter().position(|x| they_match(x, item)) {
                 added.remove(pos);
@@ -85,117 +141,89 @@ impl Diff {
             }
         }
 
+        // assets whose file didn't change can still have new settings
+        let old_assets = self.renamed_assets(new, None);
+        for asset in new._assets(None).into_values().flatten() {
+            let Some(old) = old_assets.iter().find(|old| **old == asset) else {
+                continue;
+            };
+            if let Some(description) = describe(&old.metadata, &asset.metadata) {
+                merged.push(AssetChange {
+                    description: Some(description),
+                    ..asset
+                });
+            }
+        }
+
         AssetChanges {
             added,
             removed,
-            merged: Vec::from_iter(merged),
+            merged,
         }
     }
 
     /// Return the costume differences between each sprite in two projects
     // `kind` is used to mark changes as a certain type for frontend purposes
     pub fn assets(&self, new: &Self, kind: Option<AssetChangeType>) -> Vec<AssetChange> {
-        let new_assets: Vec<AssetChange> = new
-            ._assets()
+        let new_assets: Vec<AssetChange> = new._assets(kind).into_values().flatten().collect();
+        let old_assets = self.renamed_assets(new, kind);
+
+        let _old_set = HashSet::from_iter(old_assets);
+        let _new_set = HashSet::<AssetChange>::from_iter(new_assets.clone());
+        let difference = Vec::from_iter(_new_set.difference(&_old_set));
+        new_assets
             .into_iter()
-            .map(|(sprite, changes)| {
-                changes
-                    .iter()
-                    .map(|costume| AssetChange {
-                        sprite: sprite.clone(),
-                        name: costume.0.clone(),
-                        path: costume.2.clone(),
-                        ext: costume.1.clone(),
-                        on_stage: costume.3,
-                        contents: None,
-                        kind,
-                    })
-                    .collect::<Vec<AssetChange>>()
-            })
-            .flatten()
-            .collect();
+            .filter(|x| difference.contains(&x))
+            .collect()
+    }
 
-        let old_assets: Vec<AssetChange> = self
-            ._assets()
+    /// Every costume and sound in this project, with renamed sprites under their name in a newer
+    /// project so they can be compared
+    fn renamed_assets(&self, new: &Self, kind: Option<AssetChangeType>) -> Vec<AssetChange> {
+        let renames: HashMap<String, String> = self
+            .matched_targets(new)
             .into_iter()
-            .map(|(sprite, changes)| {
-                changes
-                    .iter()
-                    .map(|costume| AssetChange {
-                        sprite: sprite.clone(),
-                        name: costume.0.clone(),
-                        path: costume.2.clone(),
-                        ext: costume.1.clone(),
-                        on_stage: costume.3,
-                        contents: None,
-                        kind,
-                    })
-                    .collect::<Vec<AssetChange>>()
+            .filter_map(|pair| match pair {
+                (Some(old), Some(new)) => Some((old.display_name(), new.display_name())),
+                _ => None,
             })
-            .flatten()
             .collect();
 
-        let _old_set = HashSet::from_iter(old_assets);
-        let _new_set = HashSet::<AssetChange>::from_iter(new_assets.clone());
-        let difference: _ = Vec::from_iter(_new_set.difference(&_old_set));
-        new_assets
+        self._assets(kind)
             .into_iter()
-            .filter(|x| difference.contains(&x))
+            .flat_map(|(sprite, changes)| {
+                let sprite = renames.get(&sprite).unwrap_or(&sprite).clone();
+                changes.into_iter().map(move |costume| AssetChange {
+                    sprite: sprite.clone(),
+                    ..costume
+                })
+            })
             .collect()
     }
 
-    /// Return the path to every costume being used
-    fn _assets(&self) -> HashMap<String, Vec<(String, String, String, bool)>> {
-        let mut assets: HashMap<String, Vec<(String, String, String, bool)>> = HashMap::new();
-        if let Some(sprites) = self.data["targets"].as_array() {
-            for sprite in sprites {
-                if let Some(sprite_costumes) = sprite["costumes"].as_array() {
-                    assets.insert(
-                        sprite["name"].as_str().unwrap().to_string()
-                            + if sprite["isStage"].as_bool().unwrap() {
-                                " (stage)"
-                            } else {
-                                ""
-                            },
-                        sprite_costumes
-                            .iter()
-                            .map(|costume| {
-                                (
-                                    costume["name"].as_str().unwrap().to_string(),
-                                    costume["dataFormat"].as_str().unwrap().to_string(),
-                                    Diff::get_asset_path(costume.clone()),
-                                    sprite["isStage"].as_bool().unwrap(),
-                                )
-                            })
-                            .collect(),
-                    );
-                }
-                if let Some(sprite_sounds) = sprite["sounds"].as_array() {
-                    assets
-                        .get_mut(
-                            &(sprite["name"].as_str().unwrap().to_string()
-                                + if sprite["isStage"].as_bool().unwrap() {
-                                    " (stage)"
-                                } else {
-                                    ""
-                                }),
-                        )
-                        .unwrap()
-                        .extend(
-                            sprite_sounds
-                                .iter()
-                                .map(|sound| {
-                                    (
-                                        sound["name"].as_str().unwrap().to_string(),
-                                        sound["dataFormat"].as_str().unwrap().to_string(),
-                                        Diff::get_asset_path(sound.clone()),
-                                        sprite["isStage"].as_bool().unwrap(),
-                                    )
-                                })
-                                .collect::<Vec<_>>(),
-                        );
-                }
-            }
+    /// Return every costume and sound being used, grouped by sprite
+    fn _assets(&self, kind: Option<AssetChangeType>) -> HashMap<String, Vec<AssetChange>> {
+        let mut assets: HashMap<String, Vec<AssetChange>> = HashMap::new();
+        for sprite in &self.data.targets {
+            let costumes = sprite.costumes.iter().map(|c| c as &dyn Asset);
+            let sounds = sprite.sounds.iter().map(|s| s as &dyn Asset);
+            assets.insert(
+                sprite.display_name(),
+                costumes
+                    .chain(sounds)
+                    .map(|asset| AssetChange {
+                        sprite: sprite.display_name(),
+                        name: asset.name().to_string(),
+                        path: asset.file_name(),
+                        ext: asset.data_format().to_string(),
+                        on_stage: sprite.is_stage,
+                        contents: None,
+                        kind,
+                        metadata: asset.metadata(),
+                        description: None,
+                    })
+                    .collect(),
+            );
         }
         assets
     }
@@ -210,8 +238,14 @@ impl Diff {
             .iter()
             .map(|change| {
                 (
-                    change.sprite.to_owned() + if change.on_stage { " (stage)" } else { "" },
-                    format!("{} {}.{}", action, change.name, change.ext),
+                    change.sprite.to_owned(),
+                    match &change.description {
+                        Some(description) => format!(
+                            "{} {}.{} ({})",
+                            action, change.name, change.ext, description
+                        ),
+                        None => format!("{} {}.{}", action, change.name, change.ext),
+                    },
                 )
             })
             .collect();
@@ -221,7 +255,7 @@ impl Diff {
                 .iter()
                 .map(|a| {
                     let parts = a.split_at(a.match_indices(" ").nth(0).unwrap().0);
-                    (parts.0.to_string(), parts.1.to_string())
+                    (parts.0.to_string(), parts.1.trim_start().to_string())
                 })
                 .collect::<Vec<_>>();
 
@@ -235,193 +269,153 @@ impl Diff {
         commits.into_iter().map(|(x, y)| (x, y)).collect()
     }
 
-    /// Formats scripts as a flat object representation with opcode, fields, and inputs
-    fn format_blocks(blocks: &Map<String, Value>) -> String {
-        let top_ids = blocks
-            .iter()
-            .filter_map(|(id, val)| {
-                if val["parent"].is_null() {
-                    Some(id)
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
-
-        let mut statements: Vec<String> = vec![];
-
-        for id in top_ids {
-            let mut _blocks: Vec<String> = vec![];
-            let mut current_block = &blocks[id];
-
-            loop {
-                let inputs =
-                    serde_json::to_string(current_block["inputs"].as_object().unwrap()).unwrap();
-                let mut _reporters = vec![];
-                let mut current_reporter = inputs;
-
-                // dirty hack to fetch inner reporters
-                loop {
-                    if let Some(mat) = REMOVE_IDS.find(&current_reporter) {
-                        let seg = &current_reporter[mat.start()..mat.end()];
-                        let seg = seg.split(",").nth(1).unwrap().replace("]}", "");
-
-                        let next_block = &blocks[&seg[1..seg.len() - 1]];
-                        let next_inputs =
-                            serde_json::to_string(next_block["inputs"].as_object().unwrap())
-                                .unwrap();
-                        let next_opcode = next_block["opcode"].as_str().unwrap();
-                        let next_fields =
-                            serde_json::to_string(next_block["fields"].as_object().unwrap())
-                                .unwrap();
-
-                        _reporters.push(format!("{} {} {}", next_opcode, next_inputs, next_fields));
-                        current_reporter = next_inputs;
-                    } else {
-                        break;
-                    };
-                }
-
-                _blocks.push(format!(
-                    "{} {} {} {}",
-                    current_block["opcode"].as_str().unwrap(),
-                    serde_json::to_string(current_block["inputs"].as_object().unwrap()).unwrap(),
-                    serde_json::to_string(current_block["fields"].as_object().unwrap()).unwrap(),
-                    _reporters.join(" ")
-                ));
-
-                let next = &current_block["next"];
-
-                // attempt to find the next block
-                match next {
-                    // block is directly below
-                    Value::String(id) => {
-                        let substack = &current_block["inputs"]["SUBSTACK"][1];
-                        match substack {
-                            Value::String(id) => current_block = &blocks[id],
-                            Value::Null => current_block = &blocks[id],
-                            _ => unreachable!(),
-                        }
-                    }
-                    // block is located in c-block or e-block
-                    Value::Null => {
-                        let substack = &current_block["inputs"]["SUBSTACK"][1];
-                        match substack {
-                            Value::String(id) => current_block = &blocks[id],
-                            Value::Null => {
-                                _blocks.push("".into());
-                                break;
-                            }
-                            _ => unreachable!(),
-                        }
-                    }
-                    _ => unreachable!(),
-                }
-            }
-            statements.push(_blocks.join("\n"));
-        }
-        statements.sort_by_key(|blocks| blocks.to_lowercase());
-
-        let blocks = &mut statements.join("\n");
-        REMOVE_IDS.replace_all(blocks, "\":[1,\"\"").to_string()
+    /// Formats scripts as an indented tree of blocks with their inputs and fields
+    fn format_blocks(target: &Target) -> String {
+        ScriptFormatter::new(target).format()
     }
 
     /// Return all script changes given a newer project
-    pub fn blocks<'a>(&'a self, cwd: &PathBuf, new: &'a Diff) -> Result<Vec<ScriptChanges>> {
-        fn _count_blocks(blocks: &Map<String, Value>) -> i32 {
-            blocks
-                .iter()
+    pub fn blocks(&self, cwd: &PathBuf, new: &Diff) -> Result<Vec<ScriptChanges>> {
+        Ok(self.script_diffs(cwd, new)?.0)
+    }
+
+    /// Return the script changes of each sprite along with every script that was added, removed
+    /// or edited, formatting each sprite's scripts only once
+    fn script_diffs(
+        &self,
+        cwd: &PathBuf,
+        new: &Diff,
+    ) -> Result<(Vec<ScriptChanges>, Vec<ScriptChange>)> {
+        fn _count_blocks(target: &Target) -> usize {
+            target
+                .blocks
+                .values()
                 .filter(|block| {
-                    block.1["opcode"]
-                        .as_str()
-                        .is_some_and(|op| !op.ends_with("_menu"))
+                    block
+                        .normal()
+                        .is_some_and(|block| !block.opcode.ends_with("_menu"))
                 })
-                .collect::<Vec<_>>()
-                .len() as i32
+                .count()
         }
 
-        let sprites = self.data["targets"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .zip_longest(new.data["targets"].as_array().unwrap())
-            .map(|x| match x {
-                Both(a, b) => (a, b),
-                Left(a) => (a, &Value::Null),
-                Right(b) => (&Value::Null, b),
-            });
-
-        let mut error = None;
-
-        let changes = sprites
-            .filter_map(|(&ref old, &ref new)| {
-                if old["blocks"].as_object() == new["blocks"].as_object() {
-                    return None;
-                }
-                if old.is_null() {
-                    return Some(ScriptChanges {
-                        sprite: new["name"].as_str().unwrap().to_string(),
-                        added: _count_blocks(&new["blocks"].as_object().unwrap()) as usize,
-                        removed: 0,
-                        on_stage: new["isStage"].as_bool().unwrap(),
+        let mut changes = vec![];
+        let mut script_changes = vec![];
+        // renamed variables are reported on their own instead of as changed blocks
+        let renamed = self.with_names_of(new);
+        // dragged scripts are reported on their own instead of as blocks added and removed
+        let moves = self.script_moves(new);
+
+        for sprite in renamed.matched_targets(new) {
+            let old_scripts = formatted_scripts(sprite.0);
+            let new_scripts = formatted_scripts(sprite.1);
+            script_changes.extend(changed_scripts(sprite, &old_scripts, &new_scripts, &moves));
+
+            let (old, new) = match sprite {
+                (Some(old), Some(new)) => (old, new),
+                (Some(old), None) => {
+                    changes.push(ScriptChanges {
+                        sprite: old.display_name(),
+                        added: 0,
+                        removed: _count_blocks(old),
+                        on_stage: old.is_stage,
+                        diff: whole_file_diff(
+                            &join_scripts(old_scripts.into_values().collect()),
+                            '-',
+                        ),
                     });
+                    continue;
                 }
-                if new.is_null() {
-                    return Some(ScriptChanges {
-                        sprite: old["name"].as_str().unwrap().to_string(),
-                        added: 0,
-                        removed: _count_blocks(old["blocks"].as_object().unwrap()) as usize,
-                        on_stage: old["isStage"].as_bool().unwrap(),
+                (None, Some(new)) => {
+                    changes.push(ScriptChanges {
+                        sprite: new.display_name(),
+                        added: _count_blocks(new),
+                        removed: 0,
+                        on_stage: new.is_stage,
+                        diff: whole_file_diff(
+                            &join_scripts(new_scripts.into_values().collect()),
+                            '+',
+                        ),
                     });
+                    continue;
                 }
+                (None, None) => continue,
+            };
 
-                let old_content = Diff::format_blocks(old["blocks"].as_object().unwrap());
-                let new_content = Diff::format_blocks(new["blocks"].as_object().unwrap());
-                println!("{}", &old_content);
-                println!("\n{}", &new_content);
-
-                let diff = git::diff(cwd, old_content, new_content, 2000);
-
-                if diff.is_err() {
-                    error = Some(diff.unwrap_err());
-                    return None;
-                };
-
-                let diff = diff.unwrap();
-
-                if diff.added != 0 || diff.removed != 0 {
-                    let name = [
-                        old["name"].as_str().unwrap(),
-                        if old["isStage"].as_bool().unwrap() {
-                            " (stage)"
-                        } else {
-                            ""
-                        },
-                    ];
-                    Some(ScriptChanges {
-                        sprite: name.join(""),
-                        added: diff.added as usize,
-                        removed: diff.removed.abs() as usize,
-                        on_stage: new["isStage"].as_bool().unwrap(),
-                    })
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
+            if old.blocks == new.blocks {
+                continue;
+            }
 
-        if let Some(error) = error {
-            return Err(error);
+            let sprite = new.display_name();
+            let unmoved = |scripts: HashMap<&str, String>, moved: Vec<&str>| {
+                join_scripts(
+                    scripts
+                        .into_iter()
+                        .filter(|(id, _)| !moved.contains(id))
+                        .map(|(_, script)| script)
+                        .collect(),
+                )
+            };
+            let old_content = unmoved(
+                old_scripts,
+                moves
+                    .iter()
+                    .filter(|m| m.action == ScriptMoveType::Move && m.from_sprite == sprite)
+                    .map(|m| m.from_id.as_str())
+                    .collect(),
+            );
+            let new_content = unmoved(
+                new_scripts,
+                moves
+                    .iter()
+                    .filter(|m| m.sprite == sprite)
+                    .map(|m| m.id.as_str())
+                    .collect(),
+            );
+            println!("{}", &old_content);
+            println!("\n{}", &new_content);
+
+            let diff = git::diff(cwd, old_content, new_content, 2000)?;
+
+            if diff.added != 0 || diff.removed != 0 {
+                changes.push(ScriptChanges {
+                    sprite: new.display_name(),
+                    added: diff.added as usize,
+                    removed: diff.removed.unsigned_abs() as usize,
+                    on_stage: new.is_stage,
+                    diff: diff.diffed,
+                });
+            }
         }
 
-        Ok(changes)
+        Ok((changes, script_changes))
     }
 
-    /// Create commits for changes from the current project to a newer one
-    pub fn commits(&self, cwd: &PathBuf, new: &Diff) -> Result<Vec<String>> {
-        let costume_changes = self._merged_costumes(&new);
-        let blocks: Vec<_> = self
-            .blocks(cwd, &new)?
+    /// Collect the changes from the current project to a newer one that make up commit messages
+    pub fn commit_parts(
+        &self,
+        cwd: &PathBuf,
+        new: &Diff,
+        settings: &ProjectSettings,
+    ) -> Result<CommitParts> {
+        let mut costume_changes = self._merged_costumes(new);
+        let moves = self.asset_moves(new);
+
+        // renamed, moved and copied assets are reported on their own
+        costume_changes.added.retain(|change| {
+            !moves.iter().any(|m| {
+                m.sprite == change.sprite && m.name == change.name && m.path == change.path
+            })
+        });
+        costume_changes.removed.retain(|change| {
+            !moves.iter().any(|m| {
+                m.removes_original()
+                    && m.from_sprite == change.sprite
+                    && m.from_name == change.name
+                    && m.path == change.path
+            })
+        });
+        let (block_changes, script_changes) = self.script_diffs(cwd, new)?;
+        let blocks: Vec<_> = block_changes
             .iter()
             .map(|s| {
                 s.format()
@@ -436,13 +430,205 @@ impl Diff {
         let removed = self.format_assets(costume_changes.removed, "remove");
         let merged = self.format_assets(costume_changes.merged, "modify");
 
-        let _commits = [blocks, added, removed, merged].concat();
+        let moved: Vec<_> = moves
+            .iter()
+            .map(|change| (change.sprite.clone(), change.format()))
+            .collect();
+
+        let script_moves = self.script_moves(new);
+        let moved_scripts = script_moves
+            .iter()
+            .map(|change| (change.sprite.clone(), change.format()))
+            .collect();
+
+        let extension_changes = self.extension_changes(new);
+        let extensions = extension_changes
+            .iter()
+            .map(|change| ("Project".to_string(), change.format()))
+            .collect();
 
-        let commits =
-            Vec::from_iter(group_items(_commits).iter().map(|(sprite, changes)| {
-                format!("{}: {}", sprite, changes.join(", ").to_string())
-            }));
+        let procedure_changes = self.procedure_changes(new);
+        let procedures = procedure_changes
+            .iter()
+            .map(|change| (change.sprite.clone(), change.format()))
+            .collect();
+
+        let monitor_changes = self.monitor_changes(new, settings.layout_changes);
+        let monitors = monitor_changes
+            .iter()
+            .map(|change| (change.sprite.clone(), change.format()))
+            .collect();
+
+        let properties = self
+            .property_changes(new)
+            .iter()
+            .map(|change| (change.sprite.clone(), change.format()))
+            .collect();
+
+        let data_changes = self.data_changes(new);
+        let data: Vec<_> = data_changes
+            .iter()
+            .map(|change| (change.sprite.clone(), change.format()))
+            .collect();
+
+        let target_changes = self.target_changes(new);
+        let sprites = target_changes
+            .iter()
+            .map(|change| (change.sprite.clone(), change.format()))
+            .collect();
+
+        let layout = match settings.layout_changes {
+            LayoutChanges::Show => self
+                .layout_changes(new)
+                .into_iter()
+                .map(|sprite| (sprite, "rearrange scripts".to_string()))
+                .collect(),
+            LayoutChanges::Silent | LayoutChanges::Ignore => vec![],
+        };
+
+        let assets = [
+            added.clone(),
+            removed.clone(),
+            moved.clone(),
+            merged.clone(),
+        ]
+        .concat();
+        let variables = data.clone();
+        let scripts = script_changes
+            .iter()
+            .map(|change| (change.sprite.clone(), change.format()))
+            .collect();
+        let comments: Vec<_> = self
+            .comment_changes(new)
+            .iter()
+            .map(|change| (change.sprite.clone(), change.format()))
+            .collect();
+
+        // anything new is a feature, while renaming and moving things around only reorganizes them
+        let adds_something = !added.is_empty()
+            || moves.iter().any(|m| !m.removes_original())
+            || script_moves
+                .iter()
+                .any(|m| m.action == ScriptMoveType::Copy)
+            || extension_changes
+                .iter()
+                .any(|c| c.action == ExtensionChangeType::Add)
+            || procedure_changes
+                .iter()
+                .any(|c| c.action == ProcedureChangeType::Add)
+            || monitor_changes
+                .iter()
+                .any(|c| c.action == MonitorChangeType::Add)
+            || data_changes.iter().any(|c| c.action == DataChangeType::Add)
+            || target_changes
+                .iter()
+                .any(|c| c.action == DataChangeType::Add)
+            || script_changes
+                .iter()
+                .any(|c| c.action == ScriptChangeType::Add);
+        let reorganized = moves.len()
+            + script_moves.len()
+            + procedure_changes
+                .iter()
+                .filter(|c| matches!(c.action, ProcedureChangeType::Rename { .. }))
+                .count()
+            + data_changes
+                .iter()
+                .map(|c| &c.action)
+                .chain(target_changes.iter().map(|c| &c.action))
+                .filter(|c| matches!(c, DataChangeType::Rename { .. }))
+                .count();
+
+        let rearranged = layout.len();
+
+        let changes = [
+            extensions,
+            sprites,
+            procedures,
+            blocks.clone(),
+            moved_scripts,
+            added,
+            removed,
+            moved,
+            merged,
+            properties,
+            monitors,
+            data,
+            layout,
+        ]
+        .concat();
+
+        let change_type = if changes.is_empty() {
+            if comments.is_empty() {
+                "chore"
+            } else {
+                "docs"
+            }
+        } else if adds_something {
+            "feat"
+        } else if changes.len() == rearranged {
+            "style"
+        } else if changes.len() == rearranged + reorganized {
+            "refactor"
+        } else {
+            "fix"
+        };
+
+        Ok(CommitParts {
+            change_type,
+            changes,
+            blocks,
+            assets,
+            variables,
+            scripts,
+            comments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A project with one sprite whose costume has the given rotation center
+    fn project(center: (i32, i32)) -> Diff {
+        let (x, y) = center;
+        let json = format!(
+            r#"{{"targets":[{{"isStage":true,"name":"Stage"}},{{"isStage":false,"name":"Cat","costumes":[{{"assetId":"cat","name":"costume1","dataFormat":"svg","rotationCenterX":{x},"rotationCenterY":{y}}}]}}]}}"#
+        );
+        Diff::from_json(&json).unwrap()
+    }
+
+    #[test]
+    fn describe_lists_changed_settings() {
+        let old = [
+            ("rotation center", "48,50".into()),
+            ("bitmap resolution", "1".into()),
+        ];
+        let new = [
+            ("rotation center", "0,0".into()),
+            ("bitmap resolution", "2".into()),
+        ];
+        assert_eq!(
+            describe(&old, &new).as_deref(),
+            Some("rotation center 48,50 -> 0,0; bitmap resolution 1 -> 2")
+        );
+        assert_eq!(describe(&old, &old), None);
+    }
 
-        Ok(commits)
+    #[test]
+    fn changed_settings_are_not_added_or_removed() {
+        let old = project((48, 50));
+        let new = project((0, 0));
+        assert!(old.assets(&new, None).is_empty());
+        assert!(new.assets(&old, None).is_empty());
+
+        let changes = old._merged_costumes(&new);
+        assert!(changes.added.is_empty() && changes.removed.is_empty());
+        assert_eq!(changes.merged.len(), 1);
+        assert_eq!(
+            changes.merged[0].description.as_deref(),
+            Some("rotation center 48,50 -> 0,0")
+        );
     }
 }
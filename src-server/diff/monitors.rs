@@ -0,0 +1,161 @@
+use serde_json::Value;
+
+use super::extensions::opcode_name;
+use super::properties::boolean;
+use super::structs::{Diff, MonitorChange, MonitorChangeType};
+use crate::config::LayoutChanges;
+use crate::sb3::{number_text, Monitor};
+
+/// Readable name of what a monitor shows
+fn monitor_name(monitor: &Monitor) -> String {
+    let param = |name: &str| match monitor.params.get(name) {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(value) => Some(value.to_string()),
+        None => None,
+    };
+    let name = match monitor.opcode.as_str() {
+        "data_variable" => param("VARIABLE"),
+        "data_listcontents" => param("LIST"),
+        "motion_xposition" => Some("x position".into()),
+        "motion_yposition" => Some("y position".into()),
+        "motion_direction" => Some("direction".into()),
+        "looks_costumenumbername" => Some("costume".into()),
+        "looks_backdropnumbername" => Some("backdrop".into()),
+        "looks_size" => Some("size".into()),
+        "sound_volume" => Some("volume".into()),
+        "sensing_answer" => Some("answer".into()),
+        "sensing_loudness" => Some("loudness".into()),
+        "sensing_timer" => Some("timer".into()),
+        "sensing_username" => Some("username".into()),
+        "sensing_current" => param("CURRENTMENU").map(|c| format!("current {}", c.to_lowercase())),
+        opcode => opcode_name(opcode),
+    };
+    name.unwrap_or(monitor.opcode.clone())
+}
+
+/// Settings of a monitor that change what players see, leaving out its value, and its position
+/// and size if `layout` is set
+fn settings(monitor: &Monitor, layout: bool) -> Vec<(&'static str, String)> {
+    let mut settings = vec![
+        ("mode", monitor.mode.clone()),
+        ("slider min", number_text(&monitor.slider_min)),
+        ("slider max", number_text(&monitor.slider_max)),
+        ("discrete", boolean(&monitor.is_discrete)),
+    ];
+    if layout {
+        settings.extend([
+            ("x", number_text(&monitor.x)),
+            ("y", number_text(&monitor.y)),
+            ("width", number_text(&monitor.width)),
+            ("height", number_text(&monitor.height)),
+        ]);
+    }
+    settings
+}
+
+impl Diff {
+    /// Return monitors that were added, removed, shown, hidden or reconfigured, matched by their
+    /// ID. Monitor values change as projects run, so they're ignored, and moving or resizing a
+    /// monitor is only reported when layout changes are shown
+    pub fn monitor_changes(&self, new: &Diff, layout: LayoutChanges) -> Vec<MonitorChange> {
+        let layout = layout == LayoutChanges::Show;
+        let stage = |diff: &Diff| {
+            diff.data
+                .stage()
+                .map(|stage| stage.display_name())
+                .unwrap_or("Stage (stage)".into())
+        };
+        let change = |diff: &Diff, monitor: &Monitor, action| MonitorChange {
+            sprite: monitor.sprite_name.clone().unwrap_or(stage(diff)),
+            id: monitor.id.clone(),
+            name: monitor_name(monitor),
+            action,
+        };
+
+        let mut changes = vec![];
+        for monitor in new.data.monitors() {
+            let Some(old) = self.data.monitors().iter().find(|m| m.id == monitor.id) else {
+                changes.push(change(new, monitor, MonitorChangeType::Add));
+                continue;
+            };
+
+            if old.visible != monitor.visible {
+                let action = if monitor.visible {
+                    MonitorChangeType::Show
+                } else {
+                    MonitorChangeType::Hide
+                };
+                changes.push(change(new, monitor, action));
+            }
+
+            let described: Vec<String> = settings(old, layout)
+                .into_iter()
+                .zip(settings(monitor, layout))
+                .filter(|((_, old), (_, new))| old != new)
+                .map(|((setting, old), (_, new))| format!("{setting} {old} -> {new}"))
+                .collect();
+            if !described.is_empty() {
+                let action = MonitorChangeType::Change { changes: described };
+                changes.push(change(new, monitor, action));
+            }
+        }
+        for monitor in self.data.monitors() {
+            if !new.data.monitors().iter().any(|m| m.id == monitor.id) {
+                changes.push(change(self, monitor, MonitorChangeType::Remove));
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A project with a visible score monitor at the given position
+    fn project(x: i32, mode: &str) -> Diff {
+        let json = format!(
+            r#"{{"targets":[{{"isStage":true,"name":"Stage"}}],"monitors":[{{"id":"score","mode":"{mode}","opcode":"data_variable","params":{{"VARIABLE":"score"}},"spriteName":null,"value":0,"x":{x},"y":5,"visible":true}}]}}"#
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    fn formatted(old: &Diff, new: &Diff, layout: LayoutChanges) -> Vec<String> {
+        old.monitor_changes(new, layout)
+            .iter()
+            .map(|change| change.format())
+            .collect()
+    }
+
+    #[test]
+    fn moved_monitors_follow_the_layout_setting() {
+        let (old, new) = (project(5, "default"), project(100, "default"));
+        assert_eq!(formatted(&old, &new, LayoutChanges::Show).len(), 1);
+        assert!(formatted(&old, &new, LayoutChanges::Silent).is_empty());
+        assert!(formatted(&old, &new, LayoutChanges::Ignore).is_empty());
+    }
+
+    #[test]
+    fn settings_are_always_reported() {
+        let (old, new) = (project(5, "default"), project(100, "large"));
+        let changes = old.monitor_changes(&new, LayoutChanges::Silent);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].action,
+            MonitorChangeType::Change {
+                changes: vec!["mode default -> large".into()]
+            }
+        );
+    }
+
+    #[test]
+    fn ignored_layout_is_restored() {
+        let mut new = project(100, "default");
+        new.restore_layout(&project(5, "default"));
+        assert_eq!(
+            new.data.monitors()[0].x,
+            project(5, "default").data.monitors()[0].x
+        );
+    }
+}
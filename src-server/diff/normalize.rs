@@ -0,0 +1,110 @@
+use super::structs::Diff;
+use crate::config::NormalizeRules;
+
+impl Diff {
+    /// Reset state that changes while a project runs to its value in an older project, leaving
+    /// anything new untouched since it was just authored
+    ///
+    /// Returns what was reset, like `position of Sprite1`, so it can be shown to the user
+    pub fn normalize(&mut self, old: &Diff, rules: &NormalizeRules) -> Vec<String> {
+        let mut reset = vec![];
+        for (i, j) in old.matched_indices(self) {
+            let (old, new) = (&old.data.targets[i], &mut self.data.targets[j]);
+            let sprite = new.display_name();
+            let mut changed = |kind: &str, changed: bool| {
+                if changed {
+                    reset.push(format!("{kind} of {sprite}"));
+                }
+            };
+
+            if rules.variables {
+                let mut values = false;
+                for (id, variable) in new.variables.iter_mut() {
+                    if let Some(old) = old.variables.get(id) {
+                        values |= variable.value != old.value;
+                        variable.value = old.value.clone();
+                    }
+                }
+                for (id, list) in new.lists.iter_mut() {
+                    if let Some(old) = old.lists.get(id) {
+                        values |= list.items != old.items;
+                        list.items = old.items.clone();
+                    }
+                }
+                changed("variable values", values);
+            }
+            if rules.positions {
+                changed("position", (&new.x, &new.y) != (&old.x, &old.y));
+                new.x = old.x.clone();
+                new.y = old.y.clone();
+            }
+            if rules.directions {
+                changed("direction", new.direction != old.direction);
+                new.direction = old.direction.clone();
+            }
+            if rules.costumes {
+                let costume = old
+                    .costumes
+                    .get(old.current_costume)
+                    .and_then(|c| new.costumes.iter().position(|n| n.asset_id == c.asset_id));
+                if let Some(costume) = costume {
+                    changed("costume", new.current_costume != costume);
+                    new.current_costume = costume;
+                }
+            }
+        }
+
+        let (mut values, mut positions) = (false, false);
+        for monitor in self.data.monitors.iter_mut().flatten() {
+            let Some(old) = old.data.monitors().iter().find(|m| m.id == monitor.id) else {
+                continue;
+            };
+            if rules.variables {
+                values |= monitor.value != old.value;
+                monitor.value = old.value.clone();
+            }
+            if rules.monitors {
+                positions |= (&monitor.x, &monitor.y) != (&old.x, &old.y);
+                monitor.x = old.x.clone();
+                monitor.y = old.y.clone();
+            }
+        }
+        if values {
+            reset.push("monitor values".into());
+        }
+        if positions {
+            reset.push("monitor positions".into());
+        }
+
+        if let (true, Some(meta)) = (rules.agent, &mut self.data.meta) {
+            if meta.agent.take().is_some() {
+                reset.push("user agent".into());
+            }
+        }
+        reset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    fn project(score: u32, x: u32) -> Diff {
+        let json = format!(
+            r#"{{"targets":[{{"isStage":false,"name":"Sprite1","variables":{{"id":["score",{score}]}},"x":{x},"y":0}}],"meta":{{"semver":"3.0.0","agent":"Mozilla/5.0"}}}}"#
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    #[test]
+    fn default_rules_reset_runtime_state_only() {
+        let mut new = project(10, 50);
+        let reset = new.normalize(&project(0, 0), &NormalizeRules::default());
+
+        assert_eq!(reset, vec!["variable values of Sprite1", "user agent"]);
+        assert_eq!(new.data.targets[0].variables["id"].value, 0);
+        assert_eq!(new.data.targets[0].x, Some(50.into()));
+        assert_eq!(new.data.meta.unwrap().agent, None);
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use super::structs::{Diff, ProcedureChange, ProcedureChangeType};
+use super::walk::{prototype, signature};
+use crate::sb3::{Block, Mutation, Target};
+
+/// Every custom block defined in a sprite, keyed by the ID of its definition block
+fn procedures(target: &Target) -> HashMap<&str, &Mutation> {
+    target
+        .blocks
+        .iter()
+        .filter_map(|(id, block)| match block {
+            Block::Normal(block) if block.opcode == "procedures_definition" => {
+                Some((id.as_str(), prototype(target, block)?))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+impl Diff {
+    /// Return custom blocks that were added, removed, renamed or had their inputs changed
+    pub fn procedure_changes(&self, new: &Diff) -> Vec<ProcedureChange> {
+        let mut changes = vec![];
+
+        for pair in self.matched_targets(new) {
+            let sprite = match pair {
+                (_, Some(target)) | (Some(target), None) => target.display_name(),
+                (None, None) => continue,
+            };
+            let old = pair.0.map(procedures).unwrap_or_default();
+            let new = pair.1.map(procedures).unwrap_or_default();
+
+            let mut change = |mutation: &Mutation, action| {
+                changes.push(ProcedureChange {
+                    sprite: sprite.clone(),
+                    name: signature(mutation),
+                    action,
+                })
+            };
+
+            let mut ids: Vec<_> = new.keys().chain(old.keys()).collect();
+            ids.sort();
+            ids.dedup();
+            for id in ids {
+                match (old.get(id), new.get(id)) {
+                    (None, Some(new)) => change(new, ProcedureChangeType::Add),
+                    (Some(old), None) => change(old, ProcedureChangeType::Remove),
+                    (Some(old), Some(new)) => {
+                        let from = signature(old);
+                        if from != signature(new) {
+                            // the same inputs in the same order means only the labels changed
+                            let action = if old.argument_ids() == new.argument_ids() {
+                                ProcedureChangeType::Rename { from }
+                            } else {
+                                ProcedureChangeType::Change { from }
+                            };
+                            change(new, action);
+                        }
+                        if old.warp() != new.warp() {
+                            change(new, ProcedureChangeType::Warp { warp: new.warp() });
+                        }
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A project with a sprite defining one custom block
+    fn project(proccode: &str, ids: &str, names: &str, warp: bool) -> Diff {
+        let mutation = format!(
+            r#"{{"tagName":"mutation","children":[],"proccode":"{proccode}","argumentids":"{ids}","argumentnames":"{names}","warp":"{warp}"}}"#
+        );
+        let json = format!(
+            r#"{{"targets":[{{"isStage":true,"name":"Stage"}},{{"isStage":false,"name":"Sprite1","blocks":{{
+                "define":{{"opcode":"procedures_definition","topLevel":true,"inputs":{{"custom_block":[1,"proto"]}}}},
+                "proto":{{"opcode":"procedures_prototype","parent":"define","shadow":true,"mutation":{mutation}}}
+            }}}}]}}"#
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    fn changes(old: &Diff, new: &Diff) -> Vec<String> {
+        old.procedure_changes(new)
+            .iter()
+            .map(|change| change.format())
+            .collect()
+    }
+
+    const ONE: &str = r#"[\"a\"]"#;
+    const TWO: &str = r#"[\"a\",\"b\"]"#;
+
+    #[test]
+    fn relabelled_blocks_are_renamed() {
+        let old = project("jump %s", ONE, r#"[\"height\"]"#, false);
+        let new = project("leap %s", ONE, r#"[\"distance\"]"#, false);
+        assert_eq!(
+            changes(&old, &new),
+            vec!["rename custom block jump (height) -> leap (distance)"]
+        );
+    }
+
+    #[test]
+    fn new_inputs_change_the_block() {
+        let old = project("jump %s", ONE, r#"[\"height\"]"#, false);
+        let new = project("jump %s if %b", TWO, r#"[\"height\",\"ready\"]"#, true);
+        assert_eq!(
+            changes(&old, &new),
+            vec![
+                "change custom block jump (height) -> jump (height) if <ready>",
+                "run custom block jump (height) if <ready> without screen refresh",
+            ]
+        );
+    }
+
+    #[test]
+    fn added_and_removed_blocks() {
+        let empty = Diff::new(
+            ProjectData::from_json(
+                r#"{"targets":[{"isStage":true,"name":"Stage"},{"isStage":false,"name":"Sprite1"}]}"#,
+            )
+            .unwrap(),
+        );
+        let jump = project("jump", "[]", "[]", false);
+        assert_eq!(changes(&empty, &jump), vec!["add custom block jump"]);
+        assert_eq!(changes(&jump, &empty), vec!["remove custom block jump"]);
+    }
+}
@@ -0,0 +1,113 @@
+use super::structs::{Diff, PropertyChange};
+use crate::sb3::{number_text, Target};
+
+pub fn boolean(value: &Option<bool>) -> String {
+    value.map(|b| b.to_string()).unwrap_or("none".into())
+}
+
+fn text(value: &Option<String>) -> String {
+    value.clone().unwrap_or("none".into())
+}
+
+/// Readable value of every property a sprite or the stage can have, in the order they appear in
+/// the editor
+fn properties(target: &Target) -> Vec<(&'static str, String)> {
+    if target.is_stage {
+        vec![
+            ("volume", number_text(&target.volume)),
+            ("tempo", number_text(&target.tempo)),
+            (
+                "video transparency",
+                number_text(&target.video_transparency),
+            ),
+            (
+                "text to speech language",
+                text(&target.text_to_speech_language.clone().flatten()),
+            ),
+        ]
+    } else {
+        vec![
+            ("size", number_text(&target.size)),
+            ("direction", number_text(&target.direction)),
+            ("rotation style", text(&target.rotation_style)),
+            ("draggable", boolean(&target.draggable)),
+            ("visible", boolean(&target.visible)),
+            (
+                "layer order",
+                target
+                    .layer_order
+                    .map(|l| l.to_string())
+                    .unwrap_or("none".into()),
+            ),
+            ("volume", number_text(&target.volume)),
+        ]
+    }
+}
+
+impl Diff {
+    /// Return sprite and stage properties that changed between sprites in both projects
+    pub fn property_changes(&self, new: &Diff) -> Vec<PropertyChange> {
+        self.matched_targets(new)
+            .into_iter()
+            .filter_map(|pair| match pair {
+                (Some(old), Some(new)) => Some((old, new)),
+                _ => None,
+            })
+            .flat_map(|(old, new)| {
+                properties(old)
+                    .into_iter()
+                    .zip(properties(new))
+                    .filter(|((_, old), (_, new))| old != new)
+                    .map(|((property, old), (_, new_value))| PropertyChange {
+                        sprite: new.display_name(),
+                        property,
+                        old,
+                        new: new_value,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A project with a stage and a sprite with the given extra properties
+    fn project(stage: &str, sprite: &str) -> Diff {
+        let json = format!(
+            r#"{{"targets":[{{"isStage":true,"name":"Stage","volume":100{stage}}},{{"isStage":false,"name":"Sprite1","visible":true{sprite}}}]}}"#
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    fn changes(old: &Diff, new: &Diff) -> Vec<String> {
+        old.property_changes(new)
+            .iter()
+            .map(|change| format!("{}: {}", change.sprite, change.format()))
+            .collect()
+    }
+
+    #[test]
+    fn changed_properties() {
+        let old = project("", r#","draggable":false,"size":100"#);
+        let new = project(r#","tempo":90"#, r#","draggable":true,"size":50"#);
+        assert_eq!(
+            changes(&old, &new),
+            vec![
+                "Stage (stage): tempo none -> 90",
+                "Sprite1: size 100 -> 50",
+                "Sprite1: draggable false -> true",
+            ]
+        );
+    }
+
+    #[test]
+    fn position_is_not_a_property() {
+        let old = project("", r#","x":0,"y":0"#);
+        let new = project("", r#","x":100,"y":-50"#);
+        assert!(changes(&old, &new).is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::structs::Diff;
+use crate::sb3::{Block, InputValue, Primitive};
+
+/// Names in a newer project by ID, kept apart by kind since a variable, list and broadcast can
+/// share an ID
+#[derive(Default)]
+struct Names<'a> {
+    variables: HashMap<&'a str, &'a str>,
+    lists: HashMap<&'a str, &'a str>,
+    broadcasts: HashMap<&'a str, &'a str>,
+}
+
+impl Names<'_> {
+    /// Names of the kind a block field refers to, like `VARIABLE` for variables
+    fn of_field(&self, field: &str) -> Option<&HashMap<&str, &str>> {
+        match field {
+            "VARIABLE" => Some(&self.variables),
+            "LIST" => Some(&self.lists),
+            "BROADCAST_OPTION" => Some(&self.broadcasts),
+            _ => None,
+        }
+    }
+}
+
+/// Point a variable, list or broadcast reporter at its newer name
+fn rename_primitive(primitive: &mut Primitive, names: &Names) {
+    let (name, id, names) = match primitive {
+        Primitive::Variable { name, id, .. } => (name, id, &names.variables),
+        Primitive::List { name, id, .. } => (name, id, &names.lists),
+        Primitive::Broadcast { name, id, .. } => (name, id, &names.broadcasts),
+        _ => return,
+    };
+    if let Some(new_name) = names.get(id.as_str()) {
+        *name = new_name.to_string();
+    }
+}
+
+impl Diff {
+    /// Copy of the project where blocks refer to variables, lists and broadcasts by their names
+    /// in a newer project
+    ///
+    /// Renaming a variable rewrites every block that uses it. Comparing scripts on this copy
+    /// leaves only changes made to the scripts themselves, since renames are reported once by
+    /// [`Diff::data_changes`]
+    pub fn with_names_of(&self, new: &Diff) -> Diff {
+        let mut names = Names::default();
+        for target in &new.data.targets {
+            names.variables.extend(
+                target
+                    .variables
+                    .iter()
+                    .map(|(id, v)| (id.as_str(), v.name.as_str())),
+            );
+            names.lists.extend(
+                target
+                    .lists
+                    .iter()
+                    .map(|(id, l)| (id.as_str(), l.name.as_str())),
+            );
+            names.broadcasts.extend(
+                target
+                    .broadcasts
+                    .iter()
+                    .map(|(id, n)| (id.as_str(), n.as_str())),
+            );
+        }
+
+        let mut renamed = Diff::new(self.data.clone());
+        for target in renamed.data.targets.iter_mut() {
+            for block in target.blocks.values_mut() {
+                let block = match block {
+                    Block::Normal(block) => block,
+                    Block::Primitive(primitive) => {
+                        rename_primitive(primitive, &names);
+                        continue;
+                    }
+                };
+                for (name, field) in block.fields.iter_mut() {
+                    let Some(names) = names.of_field(name) else {
+                        continue;
+                    };
+                    if let Some(name) = field.id.as_deref().and_then(|id| names.get(id)) {
+                        field.value = Value::String(name.to_string());
+                    }
+                }
+                for input in block.inputs.values_mut() {
+                    for value in [&mut input.value, &mut input.shadow] {
+                        if let Some(InputValue::Primitive(primitive)) = value {
+                            rename_primitive(primitive, &names);
+                        }
+                    }
+                }
+            }
+        }
+        renamed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A project whose stage has a variable, list and broadcast sharing one ID, and a sprite
+    /// using all three
+    fn project(variable: &str, list: &str, broadcast: &str) -> Diff {
+        let json = format!(
+            r#"{{"targets":[
+                {{"isStage":true,"name":"Stage","variables":{{"id":["{variable}",0]}},"lists":{{"id":["{list}",[]]}},"broadcasts":{{"id":"{broadcast}"}}}},
+                {{"isStage":false,"name":"Sprite1","blocks":{{
+                    "set":{{"opcode":"data_setvariableto","topLevel":true,"fields":{{"VARIABLE":["{variable}","id"]}},"inputs":{{"VALUE":[1,[10,"0"]]}}}},
+                    "clear":{{"opcode":"data_deletealloflist","topLevel":true,"fields":{{"LIST":["{list}","id"]}}}},
+                    "send":{{"opcode":"event_broadcast","topLevel":true,"inputs":{{"BROADCAST_INPUT":[1,[11,"{broadcast}","id"]]}}}},
+                    "reporter":[12,"{variable}","id",0,0]
+                }}}}
+            ]}}"#
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    fn scripts(diff: &Diff) -> String {
+        diff.scripts("Sprite1")
+    }
+
+    #[test]
+    fn blocks_use_the_newer_names() {
+        let old = project("score", "items", "start");
+        let new = project("points", "things", "go");
+        assert_eq!(scripts(&old.with_names_of(&new)), scripts(&new));
+    }
+
+    #[test]
+    fn kinds_sharing_an_id_keep_their_own_names() {
+        let old = project("score", "items", "start");
+        let new = project("points", "items", "start");
+        let renamed = old.with_names_of(&new);
+        assert_eq!(scripts(&renamed), scripts(&new));
+        assert!(scripts(&renamed).contains("items"));
+        assert!(scripts(&renamed).contains("start"));
+    }
+}
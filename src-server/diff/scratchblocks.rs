@@ -0,0 +1,557 @@
+use super::extensions::is_extension_opcode;
+use super::walk::{argument_kinds, field_text, fill_proccode, prototype, substacks, Walker};
+use crate::sb3::{Block, Input, InputValue, NormalBlock, Primitive, Target};
+
+const INDENT: &str = "  ";
+
+/// How a block is drawn, which decides how it's wrapped when placed in an input
+#[derive(PartialEq, Clone, Copy)]
+pub enum Shape {
+    Stack,
+    Reporter,
+    Boolean,
+}
+
+use Shape::*;
+
+/// Scratchblocks text of a built-in block, where `{NAME}` is replaced by the input or field
+/// called `NAME`
+pub fn template(opcode: &str) -> Option<(&'static str, Shape)> {
+    Some(match opcode {
+        // motion
+        "motion_movesteps" => ("move {STEPS} steps", Stack),
+        "motion_turnright" => ("turn right {DEGREES} degrees", Stack),
+        "motion_turnleft" => ("turn left {DEGREES} degrees", Stack),
+        "motion_goto" => ("go to {TO}", Stack),
+        "motion_gotoxy" => ("go to x: {X} y: {Y}", Stack),
+        "motion_glideto" => ("glide {SECS} secs to {TO}", Stack),
+        "motion_glidesecstoxy" => ("glide {SECS} secs to x: {X} y: {Y}", Stack),
+        "motion_pointindirection" => ("point in direction {DIRECTION}", Stack),
+        "motion_pointtowards" => ("point towards {TOWARDS}", Stack),
+        "motion_changexby" => ("change x by {DX}", Stack),
+        "motion_setx" => ("set x to {X}", Stack),
+        "motion_changeyby" => ("change y by {DY}", Stack),
+        "motion_sety" => ("set y to {Y}", Stack),
+        "motion_ifonedgebounce" => ("if on edge, bounce", Stack),
+        "motion_setrotationstyle" => ("set rotation style {STYLE}", Stack),
+        "motion_xposition" => ("x position", Reporter),
+        "motion_yposition" => ("y position", Reporter),
+        "motion_direction" => ("direction", Reporter),
+        // looks
+        "looks_sayforsecs" => ("say {MESSAGE} for {SECS} seconds", Stack),
+        "looks_say" => ("say {MESSAGE}", Stack),
+        "looks_thinkforsecs" => ("think {MESSAGE} for {SECS} seconds", Stack),
+        "looks_think" => ("think {MESSAGE}", Stack),
+        "looks_switchcostumeto" => ("switch costume to {COSTUME}", Stack),
+        "looks_nextcostume" => ("next costume", Stack),
+        "looks_switchbackdropto" => ("switch backdrop to {BACKDROP}", Stack),
+        "looks_switchbackdroptoandwait" => ("switch backdrop to {BACKDROP} and wait", Stack),
+        "looks_nextbackdrop" => ("next backdrop", Stack),
+        "looks_changesizeby" => ("change size by {CHANGE}", Stack),
+        "looks_setsizeto" => ("set size to {SIZE} %", Stack),
+        "looks_changeeffectby" => ("change {EFFECT} effect by {CHANGE}", Stack),
+        "looks_seteffectto" => ("set {EFFECT} effect to {VALUE}", Stack),
+        "looks_cleargraphiceffects" => ("clear graphic effects", Stack),
+        "looks_show" => ("show", Stack),
+        "looks_hide" => ("hide", Stack),
+        "looks_gotofrontback" => ("go to {FRONT_BACK} layer", Stack),
+        "looks_goforwardbackwardlayers" => ("go {FORWARD_BACKWARD} {NUM} layers", Stack),
+        "looks_costumenumbername" => ("costume {NUMBER_NAME}", Reporter),
+        "looks_backdropnumbername" => ("backdrop {NUMBER_NAME}", Reporter),
+        "looks_size" => ("size", Reporter),
+        // sound
+        "sound_playuntildone" => ("play sound {SOUND_MENU} until done", Stack),
+        "sound_play" => ("start sound {SOUND_MENU}", Stack),
+        "sound_stopallsounds" => ("stop all sounds", Stack),
+        "sound_changeeffectby" => ("change {EFFECT} effect by {VALUE}", Stack),
+        "sound_seteffectto" => ("set {EFFECT} effect to {VALUE}", Stack),
+        "sound_cleareffects" => ("clear sound effects", Stack),
+        "sound_changevolumeby" => ("change volume by {VOLUME}", Stack),
+        "sound_setvolumeto" => ("set volume to {VOLUME} %", Stack),
+        "sound_volume" => ("volume", Reporter),
+        // events
+        "event_whenflagclicked" => ("when flag clicked", Stack),
+        "event_whenkeypressed" => ("when {KEY_OPTION} key pressed", Stack),
+        "event_whenthisspriteclicked" => ("when this sprite clicked", Stack),
+        "event_whenstageclicked" => ("when stage clicked", Stack),
+        "event_whenbackdropswitchesto" => ("when backdrop switches to {BACKDROP}", Stack),
+        "event_whengreaterthan" => ("when {WHENGREATERTHANMENU} > {VALUE}", Stack),
+        "event_whenbroadcastreceived" => ("when I receive {BROADCAST_OPTION}", Stack),
+        "event_broadcast" => ("broadcast {BROADCAST_INPUT}", Stack),
+        "event_broadcastandwait" => ("broadcast {BROADCAST_INPUT} and wait", Stack),
+        // control
+        "control_wait" => ("wait {DURATION} seconds", Stack),
+        "control_repeat" => ("repeat {TIMES}", Stack),
+        "control_forever" => ("forever", Stack),
+        "control_if" | "control_if_else" => ("if {CONDITION} then", Stack),
+        "control_wait_until" => ("wait until {CONDITION}", Stack),
+        "control_repeat_until" => ("repeat until {CONDITION}", Stack),
+        "control_stop" => ("stop {STOP_OPTION}", Stack),
+        "control_start_as_clone" => ("when I start as a clone", Stack),
+        "control_create_clone_of" => ("create clone of {CLONE_OPTION}", Stack),
+        "control_delete_this_clone" => ("delete this clone", Stack),
+        // sensing
+        "sensing_touchingobject" => ("touching {TOUCHINGOBJECTMENU} ?", Boolean),
+        "sensing_touchingcolor" => ("touching color {COLOR} ?", Boolean),
+        "sensing_coloristouchingcolor" => ("color {COLOR} is touching {COLOR2} ?", Boolean),
+        "sensing_distanceto" => ("distance to {DISTANCETOMENU}", Reporter),
+        "sensing_askandwait" => ("ask {QUESTION} and wait", Stack),
+        "sensing_answer" => ("answer", Reporter),
+        "sensing_keypressed" => ("key {KEY_OPTION} pressed?", Boolean),
+        "sensing_mousedown" => ("mouse down?", Boolean),
+        "sensing_mousex" => ("mouse x", Reporter),
+        "sensing_mousey" => ("mouse y", Reporter),
+        "sensing_setdragmode" => ("set drag mode {DRAG_MODE}", Stack),
+        "sensing_loudness" => ("loudness", Reporter),
+        "sensing_timer" => ("timer", Reporter),
+        "sensing_resettimer" => ("reset timer", Stack),
+        "sensing_of" => ("{PROPERTY} of {OBJECT}", Reporter),
+        "sensing_current" => ("current {CURRENTMENU}", Reporter),
+        "sensing_dayssince2000" => ("days since 2000", Reporter),
+        "sensing_username" => ("username", Reporter),
+        // operators
+        "operator_add" => ("{NUM1} + {NUM2}", Reporter),
+        "operator_subtract" => ("{NUM1} - {NUM2}", Reporter),
+        "operator_multiply" => ("{NUM1} * {NUM2}", Reporter),
+        "operator_divide" => ("{NUM1} / {NUM2}", Reporter),
+        "operator_random" => ("pick random {FROM} to {TO}", Reporter),
+        "operator_gt" => ("{OPERAND1} > {OPERAND2}", Boolean),
+        "operator_lt" => ("{OPERAND1} < {OPERAND2}", Boolean),
+        "operator_equals" => ("{OPERAND1} = {OPERAND2}", Boolean),
+        "operator_and" => ("{OPERAND1} and {OPERAND2}", Boolean),
+        "operator_or" => ("{OPERAND1} or {OPERAND2}", Boolean),
+        "operator_not" => ("not {OPERAND}", Boolean),
+        "operator_join" => ("join {STRING1} {STRING2}", Reporter),
+        "operator_letter_of" => ("letter {LETTER} of {STRING}", Reporter),
+        "operator_length" => ("length of {STRING}", Reporter),
+        "operator_contains" => ("{STRING1} contains {STRING2} ?", Boolean),
+        "operator_mod" => ("{NUM1} mod {NUM2}", Reporter),
+        "operator_round" => ("round {NUM}", Reporter),
+        "operator_mathop" => ("{OPERATOR} of {NUM}", Reporter),
+        // variables and lists
+        "data_setvariableto" => ("set {VARIABLE} to {VALUE}", Stack),
+        "data_changevariableby" => ("change {VARIABLE} by {VALUE}", Stack),
+        "data_showvariable" => ("show variable {VARIABLE}", Stack),
+        "data_hidevariable" => ("hide variable {VARIABLE}", Stack),
+        "data_addtolist" => ("add {ITEM} to {LIST}", Stack),
+        "data_deleteoflist" => ("delete {INDEX} of {LIST}", Stack),
+        "data_deletealloflist" => ("delete all of {LIST}", Stack),
+        "data_insertatlist" => ("insert {ITEM} at {INDEX} of {LIST}", Stack),
+        "data_replaceitemoflist" => ("replace item {INDEX} of {LIST} with {ITEM}", Stack),
+        "data_itemoflist" => ("item {INDEX} of {LIST}", Reporter),
+        "data_itemnumoflist" => ("item # of {ITEM} in {LIST}", Reporter),
+        "data_lengthoflist" => ("length of {LIST}", Reporter),
+        "data_listcontainsitem" => ("{LIST} contains {ITEM} ?", Boolean),
+        "data_showlist" => ("show list {LIST}", Stack),
+        "data_hidelist" => ("hide list {LIST}", Stack),
+        // pen
+        "pen_clear" => ("erase all", Stack),
+        "pen_stamp" => ("stamp", Stack),
+        "pen_penDown" => ("pen down", Stack),
+        "pen_penUp" => ("pen up", Stack),
+        "pen_setPenColorToColor" => ("set pen color to {COLOR}", Stack),
+        "pen_changePenColorParamBy" => ("change pen {COLOR_PARAM} by {VALUE}", Stack),
+        "pen_setPenColorParamTo" => ("set pen {COLOR_PARAM} to {VALUE}", Stack),
+        "pen_changePenSizeBy" => ("change pen size by {SIZE}", Stack),
+        "pen_setPenSizeTo" => ("set pen size to {SIZE}", Stack),
+        // music
+        "music_playDrumForBeats" => ("play drum {DRUM} for {BEATS} beats", Stack),
+        "music_restForBeats" => ("rest for {BEATS} beats", Stack),
+        "music_playNoteForBeats" => ("play note {NOTE} for {BEATS} beats", Stack),
+        "music_setInstrument" => ("set instrument to {INSTRUMENT}", Stack),
+        "music_setTempo" => ("set tempo to {TEMPO}", Stack),
+        "music_changeTempo" => ("change tempo by {TEMPO}", Stack),
+        "music_getTempo" => ("tempo", Reporter),
+        // video sensing
+        "videoSensing_whenMotionGreaterThan" => ("when video motion > {REFERENCE}", Stack),
+        "videoSensing_videoOn" => ("video {ATTRIBUTE} on {SUBJECT}", Reporter),
+        "videoSensing_videoToggle" => ("turn video {VIDEO_STATE}", Stack),
+        "videoSensing_setVideoTransparency" => ("set video transparency to {TRANSPARENCY}", Stack),
+        // text to speech
+        "text2speech_speakAndWait" => ("speak {WORDS}", Stack),
+        "text2speech_setVoice" => ("set voice to {VOICE}", Stack),
+        "text2speech_setLanguage" => ("set language to {LANGUAGE}", Stack),
+        // translate
+        "translate_getTranslate" => ("translate {WORDS} to {LANGUAGE}", Reporter),
+        "translate_getViewerLanguage" => ("language", Reporter),
+        // makey makey
+        "makeymakey_whenMakeyKeyPressed" => ("when {KEY} key pressed", Stack),
+        "makeymakey_whenCodePressed" => ("when {SEQUENCE} pressed in order", Stack),
+        _ => return None,
+    })
+}
+
+/// Readable text of special menu values, like `_mouse_` for the mouse pointer
+fn menu_text(value: &str) -> &str {
+    match value {
+        "_random_" => "random position",
+        "_mouse_" => "mouse-pointer",
+        "_edge_" => "edge",
+        "_stage_" => "Stage",
+        "_myself_" => "myself",
+        value => value,
+    }
+}
+
+/// Escape characters that scratchblocks would otherwise read as the end of an input
+fn escape(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| match c {
+            '[' | ']' | '(' | ')' | '<' | '>' | '\\' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect()
+}
+
+/// Whether an input holds a boolean, which is drawn as `<>` when empty
+fn is_boolean_input(opcode: &str, name: &str) -> bool {
+    name == "CONDITION"
+        || matches!(
+            (opcode, name),
+            ("operator_and" | "operator_or", "OPERAND1" | "OPERAND2") | ("operator_not", "OPERAND")
+        )
+}
+
+/// Renders the scripts of a sprite as scratchblocks text, the syntax used to show scripts on
+/// the Scratch forums and wiki
+pub struct ScratchblocksRenderer<'a> {
+    walker: Walker<'a>,
+}
+
+impl<'a> ScratchblocksRenderer<'a> {
+    pub fn new(target: &'a Target) -> Self {
+        ScratchblocksRenderer {
+            walker: Walker::new(target),
+        }
+    }
+
+    /// Render every script in the order they're stored, separated by blank lines
+    pub fn render(mut self) -> String {
+        let mut scripts = vec![];
+        for (id, block) in self.walker.scripts() {
+            let mut lines = vec![];
+            match block {
+                Block::Normal(block) => match self.shape(block) {
+                    Stack => self.stack(id, 0, &mut lines),
+                    shape => {
+                        let Some(block) = self.walker.block(id) else {
+                            continue;
+                        };
+                        let text = self.block_text(block);
+                        lines.push(match shape {
+                            Boolean => format!("<{text}>"),
+                            _ => format!("({text})"),
+                        });
+                    }
+                },
+                Block::Primitive(primitive) => lines.push(self.primitive(primitive)),
+            }
+            scripts.push(lines.join("\n"));
+        }
+        scripts.join("\n\n")
+    }
+
+    fn shape(&self, block: &NormalBlock) -> Shape {
+        match block.opcode.as_str() {
+            "argument_reporter_string_number" | "data_variable" | "data_listcontents" => Reporter,
+            "argument_reporter_boolean" => Boolean,
+            opcode => template(opcode).map(|(_, shape)| shape).unwrap_or(
+                // unknown blocks with something below or above them must be stack blocks
+                if block.next.is_some() || block.top_level {
+                    Stack
+                } else {
+                    Reporter
+                },
+            ),
+        }
+    }
+
+    /// Render a stack of blocks starting at `id`, closing C-blocks with `end`
+    fn stack(&mut self, id: &'a str, depth: usize, lines: &mut Vec<String>) {
+        let mut current = Some(id);
+        while let Some(block) = current.and_then(|id| self.walker.block(id)) {
+            lines.push(INDENT.repeat(depth) + &self.block_text(block));
+
+            // both branches of an if/else are drawn even when empty, which leaves out their inputs
+            let branches: Vec<_> = if block.opcode == "control_if_else" {
+                ["SUBSTACK", "SUBSTACK2"]
+                    .iter()
+                    .map(|name| block.inputs.get(*name))
+                    .collect()
+            } else {
+                substacks(block)
+                    .into_iter()
+                    .map(|(_, input)| Some(input))
+                    .collect()
+            };
+            let is_c_block = !branches.is_empty()
+                || matches!(
+                    block.opcode.as_str(),
+                    "control_repeat"
+                        | "control_forever"
+                        | "control_if"
+                        | "control_if_else"
+                        | "control_repeat_until"
+                );
+
+            for (i, input) in branches.into_iter().enumerate() {
+                if i > 0 {
+                    lines.push(INDENT.repeat(depth) + "else");
+                }
+                let value = input.and_then(|input| input.value.as_ref());
+                if let Some(InputValue::Block(first)) = value {
+                    self.stack(first, depth + 1, lines);
+                }
+            }
+            if is_c_block {
+                lines.push(INDENT.repeat(depth) + "end");
+            }
+
+            current = block.next.as_deref();
+        }
+    }
+
+    /// Render a block without the brackets its shape puts around it
+    fn block_text(&mut self, block: &'a NormalBlock) -> String {
+        match block.opcode.as_str() {
+            "procedures_definition" => {
+                let Some(mutation) = prototype(self.walker.target, block) else {
+                    return "define".into();
+                };
+                let proccode = mutation.proccode.as_deref().unwrap_or("");
+                let arguments: Vec<String> = mutation
+                    .argument_names()
+                    .iter()
+                    .zip(argument_kinds(proccode))
+                    .map(|(name, kind)| match kind {
+                        'b' => format!("<{}>", escape(name)),
+                        _ => format!("({})", escape(name)),
+                    })
+                    .collect();
+                return format!("define {}", fill_proccode(proccode, &arguments));
+            }
+            "procedures_call" => {
+                let Some(mutation) = &block.mutation else {
+                    return "procedures_call".into();
+                };
+                // calls store the same proccode as their prototype, so its placeholders tell
+                // which inputs are booleans
+                let proccode = mutation.proccode.as_deref().unwrap_or("");
+
+                let mut arguments = vec![];
+                for (id, kind) in mutation.argument_ids().iter().zip(argument_kinds(proccode)) {
+                    arguments.push(self.input(block.inputs.get(id), kind == 'b'));
+                }
+                return fill_proccode(proccode, &arguments) + " :: custom";
+            }
+            "argument_reporter_string_number" | "argument_reporter_boolean" => {
+                return field_text(block, "VALUE") + " :: custom-arg";
+            }
+            "data_variable" => return field_text(block, "VARIABLE"),
+            "data_listcontents" => return field_text(block, "LIST") + " :: list",
+            _ => {}
+        }
+
+        let Some((template, _)) = template(&block.opcode) else {
+            return self.unknown(block);
+        };
+
+        let mut text = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            text += &rest[..start];
+            let end = start + rest[start..].find('}').unwrap();
+            let name = &rest[start + 1..end];
+            text += &self.slot(block, name);
+            rest = &rest[end + 1..];
+        }
+        text += rest;
+        text
+    }
+
+    /// Render an unknown block as its opcode followed by its inputs and fields, marking blocks
+    /// from extensions as such
+    fn unknown(&mut self, block: &'a NormalBlock) -> String {
+        let mut parts = vec![block.opcode.clone()];
+        for name in block.fields.keys() {
+            parts.push(self.slot(block, name));
+        }
+        for name in block.inputs.keys() {
+            if !name.starts_with("SUBSTACK") {
+                parts.push(self.slot(block, name));
+            }
+        }
+        if is_extension_opcode(&block.opcode) {
+            parts.push(":: extension".into());
+        }
+        parts.join(" ")
+    }
+
+    /// Render the input or field called `name` on a block
+    fn slot(&mut self, block: &'a NormalBlock, name: &str) -> String {
+        let boolean = is_boolean_input(&block.opcode, name);
+        if let Some(input) = block.inputs.get(name) {
+            return self.input(Some(input), boolean);
+        }
+        if block.fields.contains_key(name) {
+            let value = field_text(block, name);
+            // effects and some menus are stored in uppercase but shown in lowercase
+            let value = match name {
+                "EFFECT" | "WHENGREATERTHANMENU" | "CURRENTMENU" => value.to_lowercase(),
+                _ => value,
+            };
+            return format!("[{} v]", escape(menu_text(&value)));
+        }
+        // inputs without a shadow are left out when empty
+        if boolean { "<>" } else { "()" }.into()
+    }
+
+    /// Render what's placed in an input, with empty boolean inputs as `<>`
+    fn input(&mut self, input: Option<&'a Input>, boolean: bool) -> String {
+        let empty = if boolean { "<>" } else { "[]" };
+        match input.and_then(|input| input.value.as_ref()) {
+            None => empty.into(),
+            Some(InputValue::Primitive(primitive)) => self.primitive(primitive),
+            Some(InputValue::Block(id)) => {
+                let Some(block) = self.walker.block(id) else {
+                    return empty.into();
+                };
+                // dropdown menus are shadow blocks holding a single field
+                if block.shadow && block.inputs.is_empty() && block.fields.len() == 1 {
+                    let field = block.fields.values().next().unwrap();
+                    return format!("({} v)", escape(menu_text(&field.text())));
+                }
+                let text = self.block_text(block);
+                match self.shape(block) {
+                    Boolean => format!("<{text}>"),
+                    _ => format!("({text})"),
+                }
+            }
+        }
+    }
+
+    fn primitive(&self, primitive: &Primitive) -> String {
+        let text = escape(&primitive.text());
+        match primitive {
+            Primitive::Number(..) => format!("({text})"),
+            Primitive::Color(..) | Primitive::Text(..) => format!("[{text}]"),
+            Primitive::Broadcast { .. } => format!("({text} v)"),
+            Primitive::Variable { .. } => format!("({text})"),
+            Primitive::List { .. } => format!("({text} :: list)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Render a sprite holding the given blocks
+    fn render(blocks: &str) -> String {
+        let target: Target = serde_json::from_str(&format!(
+            r#"{{"isStage":false,"name":"Sprite1","blocks":{blocks}}}"#
+        ))
+        .unwrap();
+        ScratchblocksRenderer::new(&target).render()
+    }
+
+    /// A green flag script running an if/else with the given branches
+    fn if_else(substack: Option<&str>, substack2: Option<&str>) -> String {
+        let branch = |name: &str, id: Option<&str>| {
+            id.map(|id| format!(r#","{name}":[2,"{id}"]"#))
+                .unwrap_or_default()
+        };
+        render(&format!(
+            r#"{{
+                "flag":{{"opcode":"event_whenflagclicked","next":"if","topLevel":true,"x":0,"y":0}},
+                "if":{{"opcode":"control_if_else","parent":"flag","inputs":{{"CONDITION":[2,"down"]{}{}}}}},
+                "down":{{"opcode":"sensing_mousedown","parent":"if"}},
+                "move":{{"opcode":"motion_movesteps","parent":"if","inputs":{{"STEPS":[1,[4,"10"]]}}}},
+                "say":{{"opcode":"looks_say","parent":"if","inputs":{{"MESSAGE":[1,[10,"hi"]]}}}}
+            }}"#,
+            branch("SUBSTACK", substack),
+            branch("SUBSTACK2", substack2),
+        ))
+    }
+
+    #[test]
+    fn if_else_with_both_branches() {
+        assert_eq!(
+            if_else(Some("move"), Some("say")),
+            "when flag clicked\nif <mouse down?> then\n  move (10) steps\nelse\n  say [hi]\nend"
+        );
+    }
+
+    #[test]
+    fn if_else_with_only_else_branch() {
+        assert_eq!(
+            if_else(None, Some("say")),
+            "when flag clicked\nif <mouse down?> then\nelse\n  say [hi]\nend"
+        );
+    }
+
+    #[test]
+    fn if_else_with_only_if_branch() {
+        assert_eq!(
+            if_else(Some("move"), None),
+            "when flag clicked\nif <mouse down?> then\n  move (10) steps\nelse\nend"
+        );
+    }
+
+    #[test]
+    fn empty_boolean_inputs() {
+        let scripts = render(
+            r#"{
+                "and":{"opcode":"operator_and","topLevel":true,"x":0,"y":0},
+                "not":{"opcode":"operator_not","inputs":{"OPERAND":[2,null]},"topLevel":true,"x":0,"y":100},
+                "gt":{"opcode":"operator_gt","inputs":{"OPERAND1":[1,[10,""]],"OPERAND2":[1,[10,"50"]]},"topLevel":true,"x":0,"y":200}
+            }"#,
+        );
+        assert_eq!(scripts, "<<> and <>>\n\n<not <>>\n\n<[] > [50]>");
+    }
+
+    #[test]
+    fn custom_blocks() {
+        let scripts = render(
+            r#"{
+                "define":{"opcode":"procedures_definition","inputs":{"custom_block":[1,"prototype"]},"topLevel":true,"x":0,"y":0},
+                "prototype":{"opcode":"procedures_prototype","parent":"define","shadow":true,"mutation":{"tagName":"mutation","children":[],"proccode":"jump %s if %b","argumentids":"[\"a\",\"b\"]","argumentnames":"[\"height\",\"ready\"]","argumentdefaults":"[\"\",\"false\"]","warp":"false"}},
+                "call":{"opcode":"procedures_call","inputs":{"a":[1,[10,"10"]]},"topLevel":true,"x":0,"y":100,"mutation":{"tagName":"mutation","children":[],"proccode":"jump %s if %b","argumentids":"[\"a\",\"b\"]","warp":"false"}}
+            }"#,
+        );
+        assert_eq!(
+            scripts,
+            "define jump (height) if <ready>\n\njump [10] if <> :: custom"
+        );
+    }
+
+    #[test]
+    fn corrupt_blocks_are_skipped() {
+        let scripts = render(
+            r#"{
+                "flag":{"opcode":"event_whenflagclicked","next":"shared","topLevel":true,"x":0,"y":0},
+                "click":{"opcode":"event_whenthisspriteclicked","next":"shared","topLevel":true,"x":0,"y":0},
+                "shared":{"opcode":"looks_say","next":"gone","parent":"flag","inputs":{"MESSAGE":[3,"gone",[10,"hi"]]}}
+            }"#,
+        );
+        assert_eq!(
+            scripts,
+            "when flag clicked\nsay []\n\nwhen this sprite clicked"
+        );
+    }
+
+    #[test]
+    fn only_extension_blocks_are_marked() {
+        let scripts = render(
+            r#"{
+                "tempo":{"opcode":"music_changeTempo","inputs":{"TEMPO":[1,[4,"20"]]},"topLevel":true,"x":0,"y":0},
+                "faces":{"opcode":"faceSensing_whenFaceDetected","topLevel":true,"x":0,"y":100},
+                "old":{"opcode":"motion_scroll_right","inputs":{"DISTANCE":[1,[4,"10"]]},"topLevel":true,"x":0,"y":200}
+            }"#,
+        );
+        assert_eq!(
+            scripts,
+            "change tempo by (20)\n\nfaceSensing_whenFaceDetected :: extension\n\nmotion_scroll_right (10)"
+        );
+    }
+}
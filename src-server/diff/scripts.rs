@@ -0,0 +1,432 @@
+use super::extensions::opcode_name;
+use super::format::ScriptFormatter;
+use super::structs::{Diff, ScriptChange, ScriptChangeType, ScriptMove, ScriptMoveType};
+use super::vec_utils::line_changes;
+use super::walk::{field_text, prototype, signature};
+use crate::sb3::{Block, InputValue, NormalBlock, Target};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Text of a simple input on a block, where anything more complex than a value or menu is `(...)`
+fn input(target: &Target, block: &NormalBlock, name: &str) -> String {
+    match block
+        .inputs
+        .get(name)
+        .and_then(|input| input.value.as_ref())
+    {
+        Some(InputValue::Primitive(primitive)) => primitive.text(),
+        Some(InputValue::Block(id)) => match target.block(id).ok().and_then(Block::normal) {
+            Some(menu) if menu.shadow && menu.fields.len() == 1 => {
+                menu.fields.values().next().unwrap().text()
+            }
+            _ => "(...)".into(),
+        },
+        None => String::new(),
+    }
+}
+
+/// Readable name of a script based on its hat block, like `when I receive "game over"`
+///
+/// Scripts without a known hat are named after their first block
+pub fn script_label(target: &Target, block: &Block) -> String {
+    let Block::Normal(block) = block else {
+        return "loose reporter".into();
+    };
+    match block.opcode.as_str() {
+        "event_whenflagclicked" => "when green flag clicked".into(),
+        "event_whenkeypressed" => format!("when {} key pressed", field_text(block, "KEY_OPTION")),
+        "event_whenthisspriteclicked" => "when this sprite clicked".into(),
+        "event_whenstageclicked" => "when stage clicked".into(),
+        "event_whenbackdropswitchesto" => {
+            format!(
+                "when backdrop switches to {}",
+                field_text(block, "BACKDROP")
+            )
+        }
+        "event_whengreaterthan" => format!(
+            "when {} > {}",
+            field_text(block, "WHENGREATERTHANMENU").to_lowercase(),
+            input(target, block, "VALUE")
+        ),
+        "event_whenbroadcastreceived" => {
+            format!(
+                "when I receive \"{}\"",
+                field_text(block, "BROADCAST_OPTION")
+            )
+        }
+        "control_start_as_clone" => "when I start as a clone".into(),
+        "procedures_definition" => match prototype(target, block) {
+            Some(mutation) => format!("define {}", signature(mutation)),
+            None => "define".into(),
+        },
+        opcode => opcode_name(opcode).unwrap_or(opcode.into()),
+    }
+}
+
+/// A script that only exists on one side of a sprite, identified by its formatted content
+struct Unmatched<'a> {
+    target: &'a Target,
+    /// Name of the sprite in the newer project
+    sprite: String,
+    id: &'a str,
+    hash: u64,
+    /// Whether the script is more than a lone block, like a hat with nothing under it
+    stacked: bool,
+}
+
+/// Hash of a formatted script, which doesn't depend on block IDs or positions
+fn content_hash(script: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    script.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hash every script of a sprite, keyed by its top-level block, and tell whether it has more
+/// than one line
+fn hashed_scripts(target: &Target) -> Vec<(&str, u64, bool)> {
+    ScriptFormatter::new(target)
+        .scripts()
+        .into_iter()
+        .map(|(id, script)| (id, content_hash(&script), script.contains('\n')))
+        .collect()
+}
+
+impl Diff {
+    /// Return scripts that were dragged from one sprite to another
+    ///
+    /// Scripts are compared by content, so a script that was edited after being dragged is
+    /// still reported as a removal and an addition. Sprites that were added or removed are
+    /// ignored, so duplicating a sprite doesn't count as copying all of its scripts, and scripts
+    /// of a single block only count when they're moved, since a bare hat is often added anew
+    pub fn script_moves(&self, new: &Diff) -> Vec<ScriptMove> {
+        let mut existing = vec![];
+        let mut removed = vec![];
+        let mut added = vec![];
+        let renamed = self.with_names_of(new);
+
+        for pair in renamed.matched_targets(new) {
+            let (Some(old), Some(new)) = pair else {
+                continue;
+            };
+            let sprite = new.display_name();
+            let old_scripts = hashed_scripts(old);
+            let mut new_scripts = hashed_scripts(new);
+
+            for &(id, hash, stacked) in &old_scripts {
+                let unmatched = Unmatched {
+                    target: old,
+                    sprite: sprite.clone(),
+                    id,
+                    hash,
+                    stacked,
+                };
+                // an identical script may have been recreated with new IDs in the same sprite
+                match new_scripts.iter().position(|(_, h, _)| *h == hash) {
+                    Some(i) => {
+                        new_scripts.remove(i);
+                    }
+                    None => removed.push(unmatched),
+                }
+                existing.push(Unmatched {
+                    target: old,
+                    sprite: sprite.clone(),
+                    id,
+                    hash,
+                    stacked,
+                });
+            }
+            added.extend(
+                new_scripts
+                    .into_iter()
+                    .map(|(id, hash, stacked)| Unmatched {
+                        target: new,
+                        sprite: sprite.clone(),
+                        id,
+                        hash,
+                        stacked,
+                    }),
+            );
+        }
+
+        let mut used = vec![false; removed.len()];
+        let mut moves = vec![];
+        for script in added {
+            let moved = removed
+                .iter()
+                .enumerate()
+                .find(|(i, r)| !used[*i] && r.hash == script.hash && r.sprite != script.sprite);
+            let (from, action) = match moved {
+                Some((i, from)) => {
+                    used[i] = true;
+                    (from, ScriptMoveType::Move)
+                }
+                // a lone block like a bare hat is too common to say where it was copied from
+                None if !script.stacked => continue,
+                None => match existing
+                    .iter()
+                    .find(|e| e.hash == script.hash && e.sprite != script.sprite)
+                {
+                    Some(from) => (from, ScriptMoveType::Copy),
+                    None => continue,
+                },
+            };
+            moves.push(ScriptMove {
+                sprite: script.sprite.clone(),
+                from_sprite: from.sprite.clone(),
+                id: script.id.to_string(),
+                from_id: from.id.to_string(),
+                script: script_label(script.target, &script.target.blocks[script.id]),
+                action,
+            });
+        }
+        moves
+    }
+
+    /// Return every script that was added, removed or edited, identified by its top-level block
+    pub fn script_changes(&self, new: &Diff) -> Vec<ScriptChange> {
+        // scripts dragged between sprites are reported by script_moves
+        let moves = self.script_moves(new);
+        // renamed variables are reported on their own instead of as changed scripts
+        let renamed = self.with_names_of(new);
+
+        renamed
+            .matched_targets(new)
+            .into_iter()
+            .flat_map(|pair| {
+                let (old_scripts, new_scripts) =
+                    (formatted_scripts(pair.0), formatted_scripts(pair.1));
+                changed_scripts(pair, &old_scripts, &new_scripts, &moves)
+            })
+            .collect()
+    }
+}
+
+/// Every script of a sprite formatted on its own and keyed by its top-level block, or none if the
+/// sprite doesn't exist
+pub fn formatted_scripts(target: Option<&Target>) -> HashMap<&str, String> {
+    match target {
+        Some(target) => ScriptFormatter::new(target).scripts().into_iter().collect(),
+        None => HashMap::new(),
+    }
+}
+
+/// Scripts of a pair of sprites that were added, removed or edited, given their formatted
+/// scripts, leaving out scripts dragged between sprites
+pub fn changed_scripts(
+    pair: (Option<&Target>, Option<&Target>),
+    old_scripts: &HashMap<&str, String>,
+    new_scripts: &HashMap<&str, String>,
+    moves: &[ScriptMove],
+) -> Vec<ScriptChange> {
+    let sprite = match pair {
+        (_, Some(target)) | (Some(target), None) => target,
+        (None, None) => return vec![],
+    };
+    let mut changes = vec![];
+    let mut change = |target: &Target, id: &str, action, added, removed| {
+        let moved = moves.iter().any(|m| match action {
+            ScriptChangeType::Add => m.sprite == sprite.display_name() && m.id == id,
+            ScriptChangeType::Remove => {
+                m.action == ScriptMoveType::Move
+                    && m.from_sprite == sprite.display_name()
+                    && m.from_id == id
+            }
+            ScriptChangeType::Change => false,
+        });
+        if moved {
+            return;
+        }
+        changes.push(ScriptChange {
+            sprite: sprite.display_name(),
+            id: id.to_string(),
+            script: script_label(target, &target.blocks[id]),
+            action,
+            added,
+            removed,
+            on_stage: sprite.is_stage,
+        })
+    };
+
+    if let Some(new) = pair.1 {
+        for id in new
+            .blocks
+            .keys()
+            .filter(|id| new_scripts.contains_key(id.as_str()))
+        {
+            let new_script = &new_scripts[id.as_str()];
+            match old_scripts.get(id.as_str()) {
+                None => change(
+                    new,
+                    id,
+                    ScriptChangeType::Add,
+                    new_script.lines().count(),
+                    0,
+                ),
+                Some(old_script) if old_script != new_script => {
+                    let (added, removed) = line_changes(old_script, new_script);
+                    if added != 0 || removed != 0 {
+                        change(new, id, ScriptChangeType::Change, added, removed);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if let Some(old) = pair.0 {
+        for id in old
+            .blocks
+            .keys()
+            .filter(|id| old_scripts.contains_key(id.as_str()))
+        {
+            if !new_scripts.contains_key(id.as_str()) {
+                change(
+                    old,
+                    id,
+                    ScriptChangeType::Remove,
+                    0,
+                    old_scripts[id.as_str()].lines().count(),
+                );
+            }
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A project with sprites holding the given blocks
+    fn project(sprites: &[(&str, &str)]) -> Diff {
+        let sprites: Vec<_> = sprites
+            .iter()
+            .map(|(name, blocks)| {
+                format!(r#"{{"isStage":false,"name":"{name}","blocks":{blocks}}}"#)
+            })
+            .collect();
+        let json = format!(
+            r#"{{"targets":[{{"isStage":true,"name":"Stage"}},{}]}}"#,
+            sprites.join(",")
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    fn label(blocks: &str, id: &str) -> String {
+        let diff = project(&[("Sprite1", blocks)]);
+        let target = diff.target("Sprite1").unwrap();
+        script_label(target, &target.blocks[id])
+    }
+
+    const FLAG: &str = r#"{
+        "flag":{"opcode":"event_whenflagclicked","next":"move","topLevel":true,"x":0,"y":0},
+        "move":{"opcode":"motion_movesteps","next":"turn","parent":"flag","inputs":{"STEPS":[1,[4,"10"]]}},
+        "turn":{"opcode":"motion_turnright","parent":"move","inputs":{"DEGREES":[1,[4,"15"]]}}
+    }"#;
+
+    #[test]
+    fn scripts_are_named_by_their_hat() {
+        assert_eq!(label(FLAG, "flag"), "when green flag clicked");
+
+        let broadcast = r#"{"hat":{"opcode":"event_whenbroadcastreceived","topLevel":true,"fields":{"BROADCAST_OPTION":["game over","b"]}}}"#;
+        assert_eq!(label(broadcast, "hat"), "when I receive \"game over\"");
+
+        let define = r#"{
+            "define":{"opcode":"procedures_definition","topLevel":true,"inputs":{"custom_block":[1,"proto"]}},
+            "proto":{"opcode":"procedures_prototype","parent":"define","shadow":true,"mutation":{"tagName":"mutation","children":[],"proccode":"jump %s","argumentids":"[\"a\"]","argumentnames":"[\"height\"]","argumentdefaults":"[\"\"]","warp":"false"}}
+        }"#;
+        assert_eq!(label(define, "define"), "define jump (height)");
+    }
+
+    #[test]
+    fn scripts_without_a_hat_are_named_by_their_first_block() {
+        let pen = r#"{"pen":{"opcode":"pen_clear","topLevel":true}}"#;
+        assert_eq!(label(pen, "pen"), "erase all (Pen)");
+        assert_eq!(
+            label(
+                FLAG.replace(r#""parent":"flag","#, r#""topLevel":true,"#)
+                    .as_str(),
+                "move"
+            ),
+            "motion_movesteps"
+        );
+
+        let reporter = r#"{"x":[12,"x","var",0,0]}"#;
+        assert_eq!(label(reporter, "x"), "loose reporter");
+    }
+
+    #[test]
+    fn edited_scripts_count_their_lines() {
+        let old = project(&[("Sprite1", FLAG)]);
+        let new = project(&[("Sprite1", &FLAG.replace(r#"[4,"10"]"#, r#"[4,"20"]"#))]);
+        let changes = old.script_changes(&new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].action, ScriptChangeType::Change);
+        assert_eq!((changes[0].added, changes[0].removed), (1, 1));
+        assert_eq!(
+            changes[0].format(),
+            "change script when green flag clicked (+1, -1 blocks)"
+        );
+    }
+
+    #[test]
+    fn added_and_removed_scripts() {
+        let click = r#"{"click":{"opcode":"event_whenthisspriteclicked","topLevel":true}}"#;
+        let old = project(&[("Sprite1", FLAG)]);
+        let new = project(&[("Sprite1", click)]);
+        let changes: Vec<_> = old
+            .script_changes(&new)
+            .iter()
+            .map(|change| {
+                (
+                    change.action,
+                    change.script.clone(),
+                    change.added,
+                    change.removed,
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (
+                    ScriptChangeType::Add,
+                    "when this sprite clicked".into(),
+                    1,
+                    0
+                ),
+                (
+                    ScriptChangeType::Remove,
+                    "when green flag clicked".into(),
+                    0,
+                    3
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn copied_scripts() {
+        let old = project(&[("Sprite1", FLAG), ("Sprite2", "{}")]);
+        let new = project(&[("Sprite1", FLAG), ("Sprite2", FLAG)]);
+        let moves = old.script_moves(&new);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].action, ScriptMoveType::Copy);
+        assert_eq!(moves[0].from_sprite, "Sprite1");
+
+        // a bare hat block in another sprite isn't a copy
+        let click = r#"{"click":{"opcode":"event_whenthisspriteclicked","topLevel":true}}"#;
+        let old = project(&[("Sprite1", click), ("Sprite2", "{}")]);
+        let new = project(&[("Sprite1", click), ("Sprite2", click)]);
+        assert!(old.script_moves(&new).is_empty());
+    }
+
+    #[test]
+    fn dragged_scripts_are_not_changes() {
+        let old = project(&[("Sprite1", FLAG), ("Sprite2", "{}")]);
+        let new = project(&[("Sprite1", "{}"), ("Sprite2", FLAG)]);
+        assert!(old.script_changes(&new).is_empty());
+        assert_eq!(old.script_moves(&new).len(), 1);
+    }
+}
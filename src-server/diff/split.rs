@@ -0,0 +1,189 @@
+use super::structs::Diff;
+use crate::sb3::{Monitor, ProjectData, Target};
+
+type Pair<'a> = (Option<&'a Target>, Option<&'a Target>);
+
+/// Which parts of a newer project have been applied to an older one
+struct Applied {
+    /// Extensions, metadata, global variables, lists and broadcasts, and their monitors
+    globals: bool,
+    /// Whether each matched pair of sprites was applied
+    sprites: Vec<bool>,
+}
+
+impl Applied {
+    /// Whether the sprite a monitor belongs to was applied, looking it up in either project
+    fn monitor(&self, pairs: &[Pair], monitor: &Monitor, in_new: bool) -> bool {
+        let Some(name) = &monitor.sprite_name else {
+            return self.globals;
+        };
+        pairs
+            .iter()
+            .position(|pair| {
+                let target = if in_new { pair.1 } else { pair.0 };
+                target.is_some_and(|t| !t.is_stage && &t.name == name)
+            })
+            .is_some_and(|i| self.sprites[i])
+    }
+}
+
+/// Build the older project with some parts of the newer one applied
+fn partial(pairs: &[Pair], old: &ProjectData, new: &ProjectData, applied: &Applied) -> ProjectData {
+    let base = if applied.globals { new } else { old };
+    let mut data = ProjectData {
+        targets: vec![],
+        monitors: None,
+        extensions: base.extensions.clone(),
+        extension_urls: base.extension_urls.clone(),
+        meta: base.meta.clone(),
+        extra: base.extra.clone(),
+    };
+
+    let position = |targets: &[Target], target: &Target| {
+        targets
+            .iter()
+            .position(|t| std::ptr::eq(t, target))
+            .unwrap_or(0) as f64
+    };
+    let mut targets = vec![];
+    for (i, &(old_target, new_target)) in pairs.iter().enumerate() {
+        let target = if applied.sprites[i] {
+            new_target
+        } else {
+            old_target
+        };
+        let Some(target) = target else {
+            continue;
+        };
+        let mut target = target.clone();
+        if let (Some(new_stage), true) = (new_target, target.is_stage && applied.globals) {
+            target.variables = new_stage.variables.clone();
+            target.lists = new_stage.lists.clone();
+            target.broadcasts = new_stage.broadcasts.clone();
+        }
+        // keep the order of the newer project, with removed sprites about where they used to be
+        let order = match (new_target, old_target) {
+            (Some(new_target), _) => position(&new.targets, new_target),
+            (None, Some(old_target)) => position(&old.targets, old_target) - 0.5,
+            (None, None) => continue,
+        };
+        targets.push((order, target));
+    }
+    targets.sort_by(|a, b| a.0.total_cmp(&b.0));
+    data.targets = targets.into_iter().map(|(_, target)| target).collect();
+
+    let mut monitors = vec![];
+    for monitor in new.monitors() {
+        if applied.monitor(pairs, monitor, true) {
+            monitors.push(monitor.clone());
+        } else if let Some(old_monitor) = old
+            .monitors()
+            .iter()
+            .find(|m| m.id == monitor.id && !applied.monitor(pairs, m, false))
+        {
+            monitors.push(old_monitor.clone());
+        }
+    }
+    monitors.extend(
+        old.monitors()
+            .iter()
+            .filter(|m| !applied.monitor(pairs, m, false))
+            .filter(|m| !new.monitors().iter().any(|n| n.id == m.id))
+            .cloned(),
+    );
+    // projects without a monitors key keep going without one
+    data.monitors = (base.monitors.is_some() || !monitors.is_empty()).then_some(monitors);
+
+    data
+}
+
+impl Diff {
+    /// Split the changes to a newer project into steps that apply one part at a time, first
+    /// project-level changes like extensions and global variables, then each sprite in turn
+    ///
+    /// Every step is the whole project after its part was applied, labeled with the sprite it
+    /// changed or `None` for project-level changes. Parts that didn't change are left out
+    pub fn split_by_sprite(&self, new: &Diff) -> Vec<(Option<String>, Diff)> {
+        let pairs = self.matched_targets(new);
+        let mut applied = Applied {
+            globals: true,
+            sprites: vec![false; pairs.len()],
+        };
+        let mut previous = self.data.clone();
+        let mut steps = vec![];
+
+        for i in 0..=pairs.len() {
+            let label = match i.checked_sub(1).map(|i| pairs[i]) {
+                None => None,
+                Some((_, Some(target)) | (Some(target), None)) => {
+                    applied.sprites[i - 1] = true;
+                    Some(target.display_name())
+                }
+                Some((None, None)) => continue,
+            };
+            let step = partial(&pairs, &self.data, &new.data, &applied);
+            if step != previous {
+                previous = step.clone();
+                steps.push((label, Diff::new(step)));
+            }
+        }
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A project with a global variable and a sprite for each `(name, x)`
+    fn project(variable: &str, sprites: &[(&str, u32)]) -> Diff {
+        let sprites: Vec<_> = sprites
+            .iter()
+            .map(|(name, x)| format!(r#"{{"isStage":false,"name":"{name}","x":{x}}}"#))
+            .collect();
+        let json = format!(
+            r#"{{"targets":[{{"isStage":true,"name":"Stage","variables":{{"id":["{variable}",0]}}}},{}]}}"#,
+            sprites.join(",")
+        );
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    fn labels(steps: &[(Option<String>, Diff)]) -> Vec<Option<&str>> {
+        steps.iter().map(|(label, _)| label.as_deref()).collect()
+    }
+
+    #[test]
+    fn one_step_per_changed_part() {
+        let old = project("score", &[("Cat", 0), ("Dog", 0), ("Bird", 0)]);
+        let new = project("points", &[("Cat", 10), ("Dog", 0), ("Fish", 0)]);
+        let steps = old.split_by_sprite(&new);
+
+        assert_eq!(
+            labels(&steps),
+            vec![None, Some("Cat"), Some("Bird"), Some("Fish")]
+        );
+        // each step builds on the one before it
+        let first = &steps[0].1.data;
+        assert_eq!(first.stage().unwrap().variables["id"].name, "points");
+        assert_eq!(first.targets[1].x, Some(0.into()));
+        assert_eq!(steps[1].1.data.targets[1].x, Some(10.into()));
+        assert_eq!(steps.last().unwrap().1.data, new.data);
+    }
+
+    #[test]
+    fn nothing_changed() {
+        let old = project("score", &[("Cat", 0)]);
+        assert!(old
+            .split_by_sprite(&project("score", &[("Cat", 0)]))
+            .is_empty());
+    }
+
+    #[test]
+    fn missing_monitors_stay_missing() {
+        let old = project("score", &[("Cat", 0)]);
+        let new = project("score", &[("Cat", 5)]);
+        let steps = old.split_by_sprite(&new);
+        assert_eq!(labels(&steps), vec![Some("Cat")]);
+        assert_eq!(steps[0].1.data.monitors, None);
+    }
+}
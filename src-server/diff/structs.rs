@@ -1,5 +1,7 @@
+use std::hash::{Hash, Hasher};
+
+use crate::sb3::ProjectData;
 use serde::Serialize;
-use serde_json::Value;
 
 #[derive(Debug, Eq, Hash, PartialEq, Copy, Clone, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -9,7 +11,10 @@ pub enum AssetChangeType {
 }
 
 /// Represents a changed costume for a sprite or the stage
-#[derive(Debug, Eq, Hash, PartialEq, Clone, Serialize)]
+///
+/// Changes are compared by the asset itself, leaving out `metadata` and `description`, so
+/// changing an asset's settings doesn't make it look added or removed
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AssetChange {
     pub sprite: String,
@@ -19,6 +24,39 @@ pub struct AssetChange {
     pub on_stage: bool,
     pub kind: Option<AssetChangeType>,
     pub contents: Option<Box<[u8]>>,
+    /// Settings stored alongside the asset's file, compared when describing modified assets
+    #[serde(skip)]
+    pub metadata: Vec<(&'static str, String)>,
+    /// What changed for modified assets, like `rotation center 48,50 -> 0,0`
+    pub description: Option<String>,
+}
+
+impl AssetChange {
+    fn key(&self) -> impl PartialEq + Hash + '_ {
+        (
+            &self.sprite,
+            &self.name,
+            &self.ext,
+            &self.path,
+            self.on_stage,
+            self.kind,
+            &self.contents,
+        )
+    }
+}
+
+impl PartialEq for AssetChange {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for AssetChange {}
+
+impl Hash for AssetChange {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
 }
 
 /// Represents costumes that were added, removed, or changed
@@ -30,12 +68,15 @@ pub struct AssetChanges {
 }
 
 /// Represents a changed script for a sprite or stage, and how many blocks were added or removed
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ScriptChanges {
     pub sprite: String,
     pub added: usize,
     pub removed: usize,
     pub on_stage: bool,
+    /// Unified diff of the sprite's formatted scripts
+    pub diff: String,
 }
 
 impl ScriptChanges {
@@ -56,8 +97,456 @@ impl ScriptChanges {
     }
 }
 
+/// Represents whether something in a project was added, removed or renamed
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataChangeType {
+    Add,
+    Remove,
+    Rename { from: String },
+}
+
+/// Represents a variable, list or broadcast that was added, removed or renamed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataChange {
+    pub sprite: String,
+    /// Either `variable`, `list` or `broadcast`
+    pub kind: &'static str,
+    pub name: String,
+    pub action: DataChangeType,
+}
+
+impl DataChange {
+    /// Git commit representation of a data change, without the sprite
+    pub fn format(&self) -> String {
+        match &self.action {
+            DataChangeType::Add => format!("add {} {}", self.kind, self.name),
+            DataChangeType::Remove => format!("remove {} {}", self.kind, self.name),
+            DataChangeType::Rename { from } => {
+                format!("rename {} {} -> {}", self.kind, from, self.name)
+            }
+        }
+    }
+}
+
+/// Represents a sprite that was added, removed or renamed
+#[derive(Debug, Clone, Serialize)]
+pub struct SpriteChange {
+    pub sprite: String,
+    pub action: DataChangeType,
+}
+
+impl SpriteChange {
+    /// Git commit representation of a sprite change, without the sprite
+    pub fn format(&self) -> String {
+        match &self.action {
+            DataChangeType::Add => "add sprite".into(),
+            DataChangeType::Remove => "remove sprite".into(),
+            DataChangeType::Rename { from } => format!("rename {from} -> {}", self.sprite),
+        }
+    }
+}
+
 /// Commit generation methods for Scratch project assets and code
 #[derive(Debug)]
 pub struct Diff {
-    pub data: Value,
+    pub data: ProjectData,
+}
+
+/// Represents how a costume or sound ended up in its new place
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetMoveType {
+    Rename,
+    Duplicate,
+    Move,
+    Copy,
+}
+
+/// Represents a costume or sound that was renamed, moved to another sprite or copied, detected
+/// by the asset's content hash
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetMove {
+    /// Sprite the asset is in now
+    pub sprite: String,
+    /// Sprite the asset was in or copied from
+    pub from_sprite: String,
+    /// Either `costume` or `sound`
+    pub kind: &'static str,
+    pub name: String,
+    pub from_name: String,
+    pub path: String,
+    pub ext: String,
+    pub action: AssetMoveType,
+}
+
+impl AssetMove {
+    /// Git commit representation of an asset move, without the sprite
+    pub fn format(&self) -> String {
+        let (from, to) = (strip_stage(&self.from_sprite), strip_stage(&self.sprite));
+        match self.action {
+            AssetMoveType::Rename => {
+                format!("rename {} {} -> {}", self.kind, self.from_name, self.name)
+            }
+            AssetMoveType::Duplicate => {
+                format!(
+                    "duplicate {} {} -> {}",
+                    self.kind, self.from_name, self.name
+                )
+            }
+            AssetMoveType::Move if self.name == self.from_name => format!(
+                "move {} {}.{} from {from} to {to}",
+                self.kind, self.name, self.ext
+            ),
+            AssetMoveType::Move => format!(
+                "move {} {}.{} from {from} to {to} as {}",
+                self.kind, self.from_name, self.ext, self.name
+            ),
+            AssetMoveType::Copy => format!(
+                "copy {} {}.{} from {from} to {to}",
+                self.kind, self.from_name, self.ext
+            ),
+        }
+    }
+
+    /// Whether the original asset no longer exists
+    pub fn removes_original(&self) -> bool {
+        matches!(self.action, AssetMoveType::Rename | AssetMoveType::Move)
+    }
+}
+
+fn strip_stage(sprite: &str) -> &str {
+    sprite.strip_suffix(" (stage)").unwrap_or(sprite)
+}
+
+/// Represents whether a script was added, removed or edited
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptChangeType {
+    Add,
+    Remove,
+    Change,
+}
+
+/// Represents a single script that changed, identified by its hat block
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptChange {
+    pub sprite: String,
+    /// ID of the script's top-level block
+    pub id: String,
+    /// Readable name of the script, like `when green flag clicked` or `define jump (height)`
+    pub script: String,
+    pub action: ScriptChangeType,
+    pub added: usize,
+    pub removed: usize,
+    pub on_stage: bool,
+}
+
+impl ScriptChange {
+    /// Git commit representation of a script change, without the sprite
+    pub fn format(&self) -> String {
+        match self.action {
+            ScriptChangeType::Add => format!("add script {}", self.script),
+            ScriptChangeType::Remove => format!("remove script {}", self.script),
+            ScriptChangeType::Change => {
+                let mut counts = vec![];
+                if self.added > 0 {
+                    counts.push(format!("+{}", self.added));
+                }
+                if self.removed > 0 {
+                    counts.push(format!("-{}", self.removed));
+                }
+                format!(
+                    "change script {} ({} blocks)",
+                    self.script,
+                    counts.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// Represents whether a script left its original sprite or was duplicated into another one
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptMoveType {
+    Move,
+    Copy,
+}
+
+/// Represents a script that was dragged from one sprite to another
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptMove {
+    /// Sprite the script was dragged to
+    pub sprite: String,
+    pub from_sprite: String,
+    /// ID of the script's top-level block in the sprite it was dragged to
+    pub id: String,
+    /// ID of the script's top-level block in the sprite it came from
+    pub from_id: String,
+    /// Readable name of the script, like `when I receive "reset"`
+    pub script: String,
+    pub action: ScriptMoveType,
+}
+
+impl ScriptMove {
+    /// Git commit representation of a moved script, without the sprite
+    pub fn format(&self) -> String {
+        let (from, to) = (strip_stage(&self.from_sprite), strip_stage(&self.sprite));
+        // labels quote broadcast names, which would clash with the quotes around the label
+        let script = self.script.replace('"', "'");
+        match self.action {
+            ScriptMoveType::Move => format!("move \"{script}\" from {from} to {to}"),
+            ScriptMoveType::Copy => format!("copy \"{script}\" from {from} to {to}"),
+        }
+    }
+}
+
+/// Represents a sprite or stage property that changed, like size or tempo
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertyChange {
+    pub sprite: String,
+    /// Readable name of the property, like `rotation style`
+    pub property: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+impl PropertyChange {
+    /// Git commit representation of a property change, without the sprite
+    pub fn format(&self) -> String {
+        format!("{} {} -> {}", self.property, self.old, self.new)
+    }
+}
+
+/// Represents how a custom block changed
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcedureChangeType {
+    Add,
+    Remove,
+    /// The block's text or input names changed but its inputs didn't
+    Rename {
+        from: String,
+    },
+    /// Inputs were added, removed or reordered
+    Change {
+        from: String,
+    },
+    /// Whether the block runs without screen refresh changed
+    Warp {
+        warp: bool,
+    },
+}
+
+/// Represents a custom block that was added, removed or had its signature changed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcedureChange {
+    pub sprite: String,
+    /// Signature of the custom block, like `jump (height)`
+    pub name: String,
+    pub action: ProcedureChangeType,
+}
+
+impl ProcedureChange {
+    /// Git commit representation of a custom block change, without the sprite
+    pub fn format(&self) -> String {
+        match &self.action {
+            ProcedureChangeType::Add => format!("add custom block {}", self.name),
+            ProcedureChangeType::Remove => format!("remove custom block {}", self.name),
+            ProcedureChangeType::Rename { from } => {
+                format!("rename custom block {from} -> {}", self.name)
+            }
+            ProcedureChangeType::Change { from } => {
+                format!("change custom block {from} -> {}", self.name)
+            }
+            ProcedureChangeType::Warp { warp: true } => {
+                format!("run custom block {} without screen refresh", self.name)
+            }
+            ProcedureChangeType::Warp { warp: false } => {
+                format!("run custom block {} with screen refresh", self.name)
+            }
+        }
+    }
+}
+
+/// Represents whether an extension was added, removed or loaded from a different URL
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionChangeType {
+    Add,
+    Remove,
+    Update,
+}
+
+/// Represents an extension that changed, which applies to the whole project
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionChange {
+    pub id: String,
+    /// Readable name of the extension, like `Text to Speech`
+    pub name: String,
+    /// Where a custom extension is loaded from
+    pub url: Option<String>,
+    pub action: ExtensionChangeType,
+}
+
+impl ExtensionChange {
+    /// Git commit representation of an extension change
+    pub fn format(&self) -> String {
+        let action = match self.action {
+            ExtensionChangeType::Add => "add",
+            ExtensionChangeType::Remove => "remove",
+            ExtensionChangeType::Update => "update",
+        };
+        match &self.url {
+            Some(url) => format!("{action} extension {} ({url})", self.name),
+            None => format!("{action} extension {}", self.name),
+        }
+    }
+}
+
+/// Represents how a workspace comment changed
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentChangeType {
+    Add,
+    Remove,
+    Edit {
+        from: String,
+    },
+    /// Attached to a different script, where `None` means floating on the workspace
+    Reattach {
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+/// Represents a workspace comment that changed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentChange {
+    pub sprite: String,
+    pub id: String,
+    pub text: String,
+    pub action: CommentChangeType,
+}
+
+impl CommentChange {
+    /// Git commit representation of a comment change, without the sprite
+    pub fn format(&self) -> String {
+        let text = CommentChange::excerpt(&self.text);
+        match &self.action {
+            CommentChangeType::Add => format!("add comment {text}"),
+            CommentChangeType::Remove => format!("remove comment {text}"),
+            CommentChangeType::Edit { from } => {
+                format!("edit comment {} -> {text}", CommentChange::excerpt(from))
+            }
+            CommentChangeType::Reattach {
+                from: None,
+                to: Some(to),
+            } => {
+                format!("attach comment {text} to {to}")
+            }
+            CommentChangeType::Reattach {
+                from: Some(from),
+                to: None,
+            } => {
+                format!("detach comment {text} from {from}")
+            }
+            CommentChangeType::Reattach { from, to } => format!(
+                "move comment {text} from {} to {}",
+                from.as_deref().unwrap_or("workspace"),
+                to.as_deref().unwrap_or("workspace")
+            ),
+        }
+    }
+
+    /// First line of a comment in quotes, shortened to keep commit messages readable
+    fn excerpt(text: &str) -> String {
+        let line = text.lines().next().unwrap_or("");
+        if line.chars().count() > 40 || text.lines().count() > 1 {
+            format!("\"{}...\"", line.chars().take(40).collect::<String>())
+        } else {
+            format!("\"{line}\"")
+        }
+    }
+}
+
+/// Represents how a variable or list monitor changed
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MonitorChangeType {
+    Add,
+    Remove,
+    Show,
+    Hide,
+    /// Settings like the mode or slider range changed, described like `mode default -> slider`
+    Change {
+        changes: Vec<String>,
+    },
+}
+
+/// Represents a monitor on the stage that was added, removed or reconfigured
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorChange {
+    /// Sprite the monitor's variable belongs to, or the stage for global ones
+    pub sprite: String,
+    pub id: String,
+    /// Readable name of what the monitor shows, like a variable's name or `x position`
+    pub name: String,
+    pub action: MonitorChangeType,
+}
+
+impl MonitorChange {
+    /// Git commit representation of a monitor change, without the sprite
+    pub fn format(&self) -> String {
+        match &self.action {
+            MonitorChangeType::Add => format!("add monitor {}", self.name),
+            MonitorChangeType::Remove => format!("remove monitor {}", self.name),
+            MonitorChangeType::Show => format!("show monitor {}", self.name),
+            MonitorChangeType::Hide => format!("hide monitor {}", self.name),
+            MonitorChangeType::Change { changes } => {
+                format!("change monitor {} ({})", self.name, changes.join("; "))
+            }
+        }
+    }
+}
+
+/// Represents which blocks of a sprite changed, by block ID, for highlighting them in the editor
+#[derive(Debug, Default, Serialize)]
+pub struct BlockDiff {
+    /// Blocks only in the newer project
+    pub added: Vec<String>,
+    /// Blocks only in the older project
+    pub removed: Vec<String>,
+    /// Blocks in both projects whose opcode, fields, input values or the blocks placed in their
+    /// inputs changed
+    pub modified: Vec<String>,
+    /// Top-level blocks of scripts that were dragged around the workspace, pulled out of another
+    /// script or had blocks dragged into them
+    pub moved: Vec<String>,
+}
+
+/// Changes that can be placed in a commit message template, each as `(sprite, change)`
+#[derive(Debug, Default)]
+pub struct CommitParts {
+    /// Conventional Commits type of the changes: `feat` if anything was added, `refactor` if
+    /// things were only renamed or moved, `style` if scripts were only rearranged, `docs` for
+    /// comments, `chore` if nothing is mentioned and `fix` for anything else
+    pub change_type: &'static str,
+    /// Every change that is mentioned in the default commit subject
+    pub changes: Vec<(String, String)>,
+    pub blocks: Vec<(String, String)>,
+    pub assets: Vec<(String, String)>,
+    pub variables: Vec<(String, String)>,
+    pub scripts: Vec<(String, String)>,
+    pub comments: Vec<(String, String)>,
 }
@@ -0,0 +1,215 @@
+use std::collections::HashSet;
+
+use super::structs::{DataChangeType, Diff, SpriteChange};
+use crate::sb3::Target;
+
+/// Share of the smaller sprite's blocks two sprites need in common to be paired by similarity
+const MIN_BLOCK_OVERLAP: f64 = 0.5;
+
+/// Score how likely two sprites are the same sprite by the block IDs and asset IDs they share,
+/// since neither changes when a sprite is renamed
+///
+/// Shared blocks count before shared assets, since new sprites often start with the same
+/// costumes. Returns `None` unless enough of their blocks are shared
+fn similarity(old: &Target, new: &Target) -> Option<(usize, usize)> {
+    fn assets(t: &Target) -> HashSet<&str> {
+        t.costumes
+            .iter()
+            .map(|c| c.asset_id.as_str())
+            .chain(t.sounds.iter().map(|s| s.asset_id.as_str()))
+            .collect()
+    }
+
+    let shared_blocks = old
+        .blocks
+        .keys()
+        .filter(|id| new.blocks.contains_key(*id))
+        .count();
+    let smaller = old.blocks.len().min(new.blocks.len());
+    if shared_blocks == 0 || (shared_blocks as f64) < smaller as f64 * MIN_BLOCK_OVERLAP {
+        return None;
+    }
+    let shared_assets = assets(old).intersection(&assets(new)).count();
+    Some((shared_blocks, shared_assets))
+}
+
+impl Diff {
+    /// Pair each sprite in this project with the same sprite in a newer one
+    ///
+    /// Sprites are matched by name first, and then by the block and asset IDs they share to
+    /// catch renamed sprites, as long as most of their blocks are shared. Sprites only in this
+    /// project are paired with `None` and vice versa
+    pub fn matched_targets<'a>(
+        &'a self,
+        new: &'a Diff,
+    ) -> Vec<(Option<&'a Target>, Option<&'a Target>)> {
+        let (old_targets, new_targets) = (&self.data.targets, &new.data.targets);
+        let mut matches: Vec<Option<usize>> = vec![None; old_targets.len()];
+        let mut taken = vec![false; new_targets.len()];
+
+        for (i, old) in old_targets.iter().enumerate() {
+            let same = new_targets.iter().enumerate().position(|(j, new)| {
+                !taken[j] && new.is_stage == old.is_stage && (old.is_stage || new.name == old.name)
+            });
+            if let Some(j) = same {
+                matches[i] = Some(j);
+                taken[j] = true;
+            }
+        }
+
+        let mut candidates = vec![];
+        for (i, old) in old_targets.iter().enumerate() {
+            for (j, new) in new_targets.iter().enumerate() {
+                if matches[i].is_none() && !taken[j] && !old.is_stage && !new.is_stage {
+                    if let Some(score) = similarity(old, new) {
+                        candidates.push((score, i, j));
+                    }
+                }
+            }
+        }
+        // best matches first, keeping the project order between equally good ones
+        candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+        for (_, i, j) in candidates {
+            if matches[i].is_none() && !taken[j] {
+                matches[i] = Some(j);
+                taken[j] = true;
+            }
+        }
+
+        let mut pairs: Vec<_> = old_targets
+            .iter()
+            .zip(matches)
+            .map(|(old, j)| (Some(old), j.map(|j| &new_targets[j])))
+            .collect();
+        pairs.extend(
+            new_targets
+                .iter()
+                .zip(taken)
+                .filter(|(_, taken)| !taken)
+                .map(|(new, _)| (None, Some(new))),
+        );
+        pairs
+    }
+
+    /// Pair the positions of matching sprites in this project and a newer one, for when the newer
+    /// project needs to be changed based on this one
+    pub fn matched_indices(&self, new: &Diff) -> Vec<(usize, usize)> {
+        let index = |targets: &[Target], target: &Target| {
+            targets.iter().position(|t| std::ptr::eq(t, target))
+        };
+        self.matched_targets(new)
+            .into_iter()
+            .filter_map(|pair| match pair {
+                (Some(old), Some(new_target)) => Some((
+                    index(&self.data.targets, old)?,
+                    index(&new.data.targets, new_target)?,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Return sprites that were added, removed or renamed
+    pub fn target_changes(&self, new: &Diff) -> Vec<SpriteChange> {
+        self.matched_targets(new)
+            .into_iter()
+            .filter_map(|pair| match pair {
+                (Some(old), Some(new)) if old.name != new.name => Some(SpriteChange {
+                    sprite: new.display_name(),
+                    action: DataChangeType::Rename {
+                        from: old.display_name(),
+                    },
+                }),
+                (Some(old), None) => Some(SpriteChange {
+                    sprite: old.display_name(),
+                    action: DataChangeType::Remove,
+                }),
+                (None, Some(new)) => Some(SpriteChange {
+                    sprite: new.display_name(),
+                    action: DataChangeType::Add,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sb3::ProjectData;
+
+    /// A sprite with a block for each ID and the cat costume every new sprite starts with
+    fn sprite(name: &str, blocks: &[&str]) -> String {
+        let blocks: Vec<_> = blocks
+            .iter()
+            .map(|id| format!(r#""{id}":{{"opcode":"motion_ifonedgebounce","topLevel":true}}"#))
+            .collect();
+        format!(
+            r#"{{"isStage":false,"name":"{name}","blocks":{{{}}},"costumes":[{{"assetId":"cat","name":"costume1","dataFormat":"svg"}}]}}"#,
+            blocks.join(",")
+        )
+    }
+
+    fn project(sprites: &[String]) -> Diff {
+        let stage = r#"{"isStage":true,"name":"Stage"}"#;
+        let json = format!(r#"{{"targets":[{stage},{}]}}"#, sprites.join(","));
+        Diff::new(ProjectData::from_json(&json).unwrap())
+    }
+
+    /// Names of the paired sprites, leaving out the stage
+    fn pairs(old: &Diff, new: &Diff) -> Vec<(Option<String>, Option<String>)> {
+        old.matched_targets(new)
+            .into_iter()
+            .skip(1)
+            .map(|(old, new)| (old.map(|t| t.name.clone()), new.map(|t| t.name.clone())))
+            .collect()
+    }
+
+    fn named(old: Option<&str>, new: Option<&str>) -> (Option<String>, Option<String>) {
+        (old.map(String::from), new.map(String::from))
+    }
+
+    #[test]
+    fn renamed_sprite_is_paired() {
+        let old = project(&[sprite("Cat", &["a", "b", "c"])]);
+        let new = project(&[sprite("Kitten", &["a", "b", "d"])]);
+        assert_eq!(pairs(&old, &new), vec![named(Some("Cat"), Some("Kitten"))]);
+    }
+
+    #[test]
+    fn shared_costume_is_not_enough() {
+        let old = project(&[sprite("Cat", &["a"])]);
+        let new = project(&[sprite("Dog", &["b"])]);
+        assert_eq!(
+            pairs(&old, &new),
+            vec![named(Some("Cat"), None), named(None, Some("Dog"))]
+        );
+    }
+
+    #[test]
+    fn few_shared_blocks_are_not_enough() {
+        let old = project(&[sprite("Cat", &["a", "b", "c", "d"])]);
+        let new = project(&[sprite("Dog", &["a", "e", "f", "g"])]);
+        assert_eq!(
+            pairs(&old, &new),
+            vec![named(Some("Cat"), None), named(None, Some("Dog"))]
+        );
+    }
+
+    #[test]
+    fn most_shared_blocks_win() {
+        let old = project(&[sprite("Cat", &["a", "b", "c", "d"])]);
+        let new = project(&[
+            sprite("Copy", &["a", "b", "e"]),
+            sprite("Kitten", &["a", "b", "c", "d"]),
+        ]);
+        assert_eq!(
+            pairs(&old, &new),
+            vec![
+                named(Some("Cat"), Some("Kitten")),
+                named(None, Some("Copy"))
+            ]
+        );
+    }
+}
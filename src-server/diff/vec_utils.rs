@@ -37,6 +37,31 @@ impl ItemGrouping for Vec<Vec<String>> {
     }
 }
 
+/// Count the lines added and removed between two texts, using their longest common subsequence
+/// of lines like `git diff` does
+pub fn line_changes(old: &str, new: &str) -> (usize, usize) {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+
+    // common[j] holds the longest common subsequence of the lines seen so far and new[..j]
+    let mut common = vec![0; new.len() + 1];
+    for old_line in &old {
+        let mut diagonal = 0;
+        for (j, new_line) in new.iter().enumerate() {
+            let above = common[j + 1];
+            common[j + 1] = if old_line == new_line {
+                diagonal + 1
+            } else {
+                above.max(common[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    let kept = common[new.len()];
+    (new.len() - kept, old.len() - kept)
+}
+
 /// Set-intersection of costume changes because HashSet::intersection sucks
 ///
 /// Returns costumes present in all sets that have the same costume name and original sprite.
@@ -0,0 +1,141 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::sb3::{Block, Input, InputValue, Mutation, NormalBlock, Target};
+
+/// Follows the blocks of a sprite's scripts for the formatters, visiting each block once
+///
+/// Blocks that are missing or reachable from more than one place, which only happen in corrupt
+/// projects, are skipped instead of failing the whole sprite
+pub struct Walker<'a> {
+    pub target: &'a Target,
+    visited: HashSet<&'a str>,
+    /// Custom block prototypes by proccode, used to look up the arguments of calls
+    pub prototypes: HashMap<&'a str, &'a Mutation>,
+}
+
+impl<'a> Walker<'a> {
+    pub fn new(target: &'a Target) -> Self {
+        let prototypes = target
+            .blocks
+            .values()
+            .filter_map(Block::normal)
+            .filter(|block| block.opcode == "procedures_prototype")
+            .filter_map(|block| block.mutation.as_ref())
+            .filter_map(|mutation| Some((mutation.proccode.as_deref()?, mutation)))
+            .collect();
+
+        Walker {
+            target,
+            visited: HashSet::new(),
+            prototypes,
+        }
+    }
+
+    /// The top-level block of every script, in the order they're stored
+    pub fn scripts(&self) -> impl Iterator<Item = (&'a str, &'a Block)> {
+        self.target
+            .blocks
+            .iter()
+            .filter(|(_, block)| block.is_top_level())
+            .map(|(id, block)| (id.as_str(), block))
+    }
+
+    /// Look up a block by ID, skipping missing blocks, reporters placed where a block is expected
+    /// and blocks that were already visited, which also stops cycles
+    pub fn block(&mut self, id: &'a str) -> Option<&'a NormalBlock> {
+        if !self.visited.insert(id) {
+            return None;
+        }
+        self.target.blocks.get(id)?.normal()
+    }
+}
+
+/// The inputs holding the stacks inside a C-block, with `SUBSTACK` before `SUBSTACK2`
+pub fn substacks(block: &NormalBlock) -> Vec<(&str, &Input)> {
+    let mut substacks: Vec<_> = block
+        .inputs
+        .iter()
+        .filter(|(name, _)| name.starts_with("SUBSTACK"))
+        .map(|(name, input)| (name.as_str(), input))
+        .collect();
+    substacks.sort_by_key(|(name, _)| *name);
+    substacks
+}
+
+/// Text of a field on a block, or an empty string if it doesn't exist
+pub fn field_text(block: &NormalBlock, name: &str) -> String {
+    block
+        .fields
+        .get(name)
+        .map(|field| field.text())
+        .unwrap_or_default()
+}
+
+/// The mutation of a custom block definition's prototype, which holds the block's signature
+pub fn prototype<'a>(target: &'a Target, definition: &NormalBlock) -> Option<&'a Mutation> {
+    let Some(InputValue::Block(id)) = definition
+        .inputs
+        .get("custom_block")
+        .and_then(|input| input.value.as_ref())
+    else {
+        return None;
+    };
+    target.block(id).ok()?.normal()?.mutation.as_ref()
+}
+
+/// Readable signature of a custom block, like `jump (height)`
+pub fn signature(mutation: &Mutation) -> String {
+    render_proccode(
+        mutation.proccode.as_deref().unwrap_or(""),
+        &mutation.argument_names(),
+    )
+}
+
+/// The kind of each argument placeholder in a proccode, `b` for booleans and `s` otherwise
+pub fn argument_kinds(proccode: &str) -> Vec<char> {
+    let mut kinds = vec![];
+    let mut chars = proccode.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let ('%', Some(&kind @ ('s' | 'n' | 'b'))) = (c, chars.peek()) {
+            chars.next();
+            kinds.push(if kind == 'b' { 'b' } else { 's' });
+        }
+    }
+    kinds
+}
+
+/// Fill a proccode's `%s`, `%n` and `%b` placeholders with already rendered arguments, leaving
+/// missing ones empty
+pub fn fill_proccode(proccode: &str, arguments: &[String]) -> String {
+    let mut arguments = arguments.iter();
+    let mut filled = String::new();
+    let mut chars = proccode.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('%', Some(&kind @ ('s' | 'n' | 'b'))) => {
+                chars.next();
+                filled += &match arguments.next() {
+                    Some(argument) => argument.clone(),
+                    None if kind == 'b' => "<>".into(),
+                    None => "()".into(),
+                };
+            }
+            _ => filled.push(c),
+        }
+    }
+    filled
+}
+
+/// Replace the argument placeholders of a custom block's proccode with its argument names,
+/// e.g. `jump %s` with `height` becomes `jump (height)`
+pub fn render_proccode(proccode: &str, names: &[String]) -> String {
+    let arguments: Vec<_> = names
+        .iter()
+        .zip(argument_kinds(proccode))
+        .map(|(name, kind)| match kind {
+            'b' => format!("<{name}>"),
+            _ => format!("({name})"),
+        })
+        .collect();
+    fill_proccode(proccode, &arguments)
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
@@ -151,6 +152,18 @@ pub fn show_revision(cwd: &PathBuf, commit: &str) -> Result<String> {
     Ok(String::from_utf8_lossy(&proc.stdout).to_string())
 }
 
+/// Fetch the raw contents of a file at a certain revision
+pub fn show_file(cwd: &PathBuf, revision: &str, path: &str) -> Result<Vec<u8>> {
+    let output = run(vec!["show", &format!("{revision}:{path}")], Some(cwd)).output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "failed to read {path} at {revision}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(output.stdout)
+}
+
 pub fn main_branch(cwd: &PathBuf) -> Result<String> {
     let git_branch = &String::from_utf8(
         run(vec!["branch", "-rl", "*/HEAD"], Some(cwd))
@@ -186,3 +199,587 @@ pub fn run(args: Vec<&str>, cwd: Option<&PathBuf>) -> Command {
 
     cmd
 }
+
+/// Represents a single changed path from `git status --porcelain=v2`
+#[derive(Serialize, Debug, Clone)]
+pub struct FileStatus {
+    pub path: String,
+    pub orig_path: Option<String>,
+    /// Two-letter `XY` code for the index and worktree, e.g. `.M` or `A.`
+    pub code: String,
+    pub state: &'static str,
+}
+
+/// Represents the branch and file state of a repository
+#[derive(Serialize, Debug, Default)]
+pub struct RepoStatus {
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub in_progress: Option<&'static str>,
+    pub files: Vec<FileStatus>,
+}
+
+/// Describe an `XY` status code in a single word
+///
+/// The index state comes first, except that a file deleted from the worktree is deleted, even
+/// if it was added to the index before
+fn file_state(code: &str) -> &'static str {
+    let mut chars = code.chars();
+    let (x, y) = (chars.next().unwrap_or('.'), chars.next().unwrap_or('.'));
+    match (x, y) {
+        ('A', 'D') => "deleted",
+        ('A', _) => "added",
+        ('R', _) => "renamed",
+        ('C', _) => "copied",
+        ('D', _) | (_, 'D') => "deleted",
+        ('.', 'A') => "added",
+        ('.', 'R') => "renamed",
+        ('.', 'C') => "copied",
+        _ => "modified",
+    }
+}
+
+/// Return which multi-step operation (rebase, merge, ...) is currently in progress, if any
+fn operation_in_progress(cwd: &PathBuf) -> Result<Option<&'static str>> {
+    let git_dir = String::from_utf8(
+        run(vec!["rev-parse", "--git-dir"], Some(cwd))
+            .output()?
+            .stdout,
+    )?;
+    let git_dir = cwd.join(git_dir.trim());
+
+    let operations = [
+        ("rebase-merge", "rebase"),
+        ("rebase-apply", "rebase"),
+        ("MERGE_HEAD", "merge"),
+        ("CHERRY_PICK_HEAD", "cherry-pick"),
+        ("REVERT_HEAD", "revert"),
+    ];
+
+    Ok(operations
+        .into_iter()
+        .find(|(file, _)| git_dir.join(file).exists())
+        .map(|(_, operation)| operation))
+}
+
+/// Parse the branch and file state of a repository using `git status --porcelain=v2`
+pub fn status(cwd: &PathBuf) -> Result<RepoStatus> {
+    let output = run(
+        vec![
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "-z",
+            "--untracked-files=all",
+        ],
+        Some(cwd),
+    )
+    .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git status failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let mut status = parse_status(&String::from_utf8(output.stdout)?)?;
+    status.in_progress = operation_in_progress(cwd)?;
+    Ok(status)
+}
+
+/// Parse the output of `git status --porcelain=v2 --branch -z`
+fn parse_status(stdout: &str) -> Result<RepoStatus> {
+    let mut status = RepoStatus::default();
+
+    // entries are NUL-separated, and renames carry their original path in the following entry
+    let mut entries = stdout.split('\0').filter(|e| !e.is_empty());
+    while let Some(entry) = entries.next() {
+        if let Some(header) = entry.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.head" if value != "(detached)" => status.branch = Some(value.into()),
+                "branch.upstream" => status.upstream = Some(value.into()),
+                "branch.ab" => {
+                    for count in value.split(' ') {
+                        if let Some(ahead) = count.strip_prefix('+') {
+                            status.ahead = ahead.parse()?;
+                        } else if let Some(behind) = count.strip_prefix('-') {
+                            status.behind = behind.parse()?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let fields: Vec<&str> = entry.splitn(11, ' ').collect();
+        let file = match fields[0] {
+            "1" if fields.len() >= 9 => FileStatus {
+                path: fields[8..].join(" "),
+                orig_path: None,
+                code: fields[1].into(),
+                state: file_state(fields[1]),
+            },
+            "2" if fields.len() >= 10 => FileStatus {
+                path: fields[9..].join(" "),
+                orig_path: entries.next().map(|p| p.to_string()),
+                code: fields[1].into(),
+                state: file_state(fields[1]),
+            },
+            "u" if fields.len() >= 11 => FileStatus {
+                path: fields[10].into(),
+                orig_path: None,
+                code: fields[1].into(),
+                state: "conflicted",
+            },
+            "?" => FileStatus {
+                path: entry[2..].into(),
+                orig_path: None,
+                code: "??".into(),
+                state: "untracked",
+            },
+            _ => continue,
+        };
+        status.files.push(file);
+    }
+
+    Ok(status)
+}
+
+/// Represents the author of a commit
+#[derive(Serialize, Debug)]
+pub struct Author {
+    pub name: String,
+    pub email: String,
+    pub date: String,
+}
+
+/// Represents a single commit from `git log`
+#[derive(Serialize, Debug)]
+pub struct Commit {
+    pub commit: String,
+    pub subject: String,
+    pub body: String,
+    pub author: Author,
+}
+
+/// Filters and pagination for `git log`
+#[derive(Debug, Default)]
+pub struct LogFilter<'a> {
+    pub skip: Option<usize>,
+    pub limit: Option<usize>,
+    pub author: Option<&'a str>,
+    pub since: Option<&'a str>,
+    pub until: Option<&'a str>,
+    pub text: Option<&'a str>,
+    /// Only list commits that changed this file
+    pub path: Option<&'a str>,
+}
+
+/// List commits reachable from HEAD, newest first
+pub fn log(cwd: &PathBuf, filter: &LogFilter) -> Result<Vec<Commit>> {
+    // every field is NUL-terminated and -z separates commits with NUL,
+    // so messages can contain anything except NUL
+    let mut args = vec![
+        "log".to_string(),
+        "-z".into(),
+        "--pretty=format:%H%x00%s%x00%b%x00%aN%x00%aE%x00%aD".into(),
+    ];
+    if let Some(skip) = filter.skip {
+        args.push(format!("--skip={skip}"));
+    }
+    if let Some(limit) = filter.limit {
+        args.push(format!("--max-count={limit}"));
+    }
+    if let Some(author) = filter.author {
+        args.push(format!("--author={author}"));
+    }
+    if let Some(since) = filter.since {
+        args.push(format!("--since={since}"));
+    }
+    if let Some(until) = filter.until {
+        args.push(format!("--until={until}"));
+    }
+    if let Some(text) = filter.text {
+        args.extend([format!("--grep={text}"), "--regexp-ignore-case".into()]);
+    }
+    // search for the author and message as typed instead of as regular expressions
+    if filter.author.is_some() || filter.text.is_some() {
+        args.push("--fixed-strings".into());
+    }
+    if let Some(path) = filter.path {
+        args.extend(["--".into(), path.into()]);
+    }
+
+    let output = run(args.iter().map(|a| a.as_str()).collect(), Some(cwd)).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // a repository without any commits has no log to show
+        if stderr.contains("does not have any commits") {
+            return Ok(vec![]);
+        }
+        return Err(anyhow!("git log failed: {stderr}"));
+    }
+
+    Ok(parse_log(&String::from_utf8(output.stdout)?))
+}
+
+/// Parse commits written by `log` with every field followed by a NUL
+fn parse_log(stdout: &str) -> Vec<Commit> {
+    if stdout.is_empty() {
+        return vec![];
+    }
+
+    let fields: Vec<&str> = stdout.split('\0').collect();
+    fields
+        .chunks_exact(6)
+        .map(|c| Commit {
+            commit: c[0].trim_start_matches('\n').into(),
+            subject: c[1].into(),
+            body: c[2].trim_end().into(),
+            author: Author {
+                name: c[3].into(),
+                email: c[4].into(),
+                date: c[5].into(),
+            },
+        })
+        .collect()
+}
+
+/// Represents the contents of a file before and after a commit, as blob IDs
+///
+/// `old` is `None` when the commit added the file, like the root commit does
+#[derive(Debug, PartialEq)]
+pub struct FileChange {
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// Find the blobs a file had before and after every commit that changed it, keyed by commit
+///
+/// Merge commits aren't included since they have no single parent to compare against
+pub fn file_changes(cwd: &PathBuf, path: &str) -> Result<HashMap<String, FileChange>> {
+    let output = run(
+        vec![
+            "log",
+            "-z",
+            "--format=%H",
+            "--raw",
+            "--no-abbrev",
+            "--",
+            path,
+        ],
+        Some(cwd),
+    )
+    .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("does not have any commits") {
+            return Ok(HashMap::new());
+        }
+        return Err(anyhow!("git log failed: {stderr}"));
+    }
+    Ok(parse_file_changes(&String::from_utf8(output.stdout)?))
+}
+
+/// Parse the output of `git log -z --format=%H --raw --no-abbrev`, where each commit hash is
+/// followed by `:<old mode> <new mode> <old blob> <new blob> <status>` and the path
+fn parse_file_changes(stdout: &str) -> HashMap<String, FileChange> {
+    let blob = |id: &str| (!id.chars().all(|c| c == '0')).then(|| id.to_string());
+
+    let mut changes = HashMap::new();
+    let mut commit = None;
+    let mut entries = stdout
+        .split('\0')
+        .map(|e| e.trim_start_matches('\n'))
+        .filter(|e| !e.is_empty());
+    while let Some(entry) = entries.next() {
+        let Some(raw) = entry.strip_prefix(':') else {
+            commit = Some(entry.to_string());
+            continue;
+        };
+        // the path follows in its own entry
+        entries.next();
+        let fields: Vec<&str> = raw.split(' ').collect();
+        if let (Some(commit), [_, _, old, new, ..]) = (&commit, fields.as_slice()) {
+            changes.insert(
+                commit.clone(),
+                FileChange {
+                    old: blob(old),
+                    new: blob(new),
+                },
+            );
+        }
+    }
+    changes
+}
+
+/// Represents a single stash entry
+#[derive(Serialize, Debug)]
+pub struct StashEntry {
+    pub index: usize,
+    pub message: String,
+    pub date: String,
+}
+
+/// List stashed changes, newest first
+pub fn stash_list(cwd: &PathBuf) -> Result<Vec<StashEntry>> {
+    let output = run(
+        vec!["stash", "list", "-z", "--format=%gs%x00%cD"],
+        Some(cwd),
+    )
+    .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git stash list failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(parse_stash_list(&String::from_utf8(output.stdout)?))
+}
+
+/// Parse `git stash list -z --format=%gs%x00%cD`, where every field ends with a NUL
+fn parse_stash_list(stdout: &str) -> Vec<StashEntry> {
+    if stdout.is_empty() {
+        return vec![];
+    }
+
+    let fields: Vec<&str> = stdout.split('\0').collect();
+    fields
+        .chunks_exact(2)
+        .enumerate()
+        .map(|(index, c)| StashEntry {
+            index,
+            message: c[0].trim_start_matches('\n').into(),
+            date: c[1].into(),
+        })
+        .collect()
+}
+
+/// Create an empty repository in the temporary directory with a committer set up, replacing
+/// any left over from an earlier run
+#[cfg(test)]
+pub fn temp_repo(name: &str) -> PathBuf {
+    let pth = std::env::temp_dir().join(format!("scratch-git-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&pth);
+    std::fs::create_dir_all(&pth).unwrap();
+    for args in [
+        vec!["init", "-q"],
+        vec!["config", "user.name", "Test"],
+        vec!["config", "user.email", "test@example.com"],
+    ] {
+        assert!(run(args, Some(&pth)).status().unwrap().success());
+    }
+    pth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_state_prefers_index() {
+        assert_eq!(file_state("A."), "added");
+        assert_eq!(file_state("AM"), "added");
+        assert_eq!(file_state("AD"), "deleted");
+        assert_eq!(file_state("M."), "modified");
+        assert_eq!(file_state(".M"), "modified");
+        assert_eq!(file_state("MD"), "deleted");
+        assert_eq!(file_state(".D"), "deleted");
+        assert_eq!(file_state("D."), "deleted");
+        assert_eq!(file_state("R."), "renamed");
+        assert_eq!(file_state("RM"), "renamed");
+    }
+
+    #[test]
+    fn parse_stash_entries() {
+        // with -z, git separates entries with a NUL and a newline
+        let stdout = "On main: WIP\0Mon, 19 Oct 2026 10:00:00 +0200\0\nOn main: sprites\0Sun, 18 Oct 2026 09:00:00 +0200\0";
+        let stashes = parse_stash_list(stdout);
+        assert_eq!(stashes.len(), 2);
+        assert_eq!(
+            (
+                stashes[0].index,
+                stashes[0].message.as_str(),
+                stashes[0].date.as_str()
+            ),
+            (0, "On main: WIP", "Mon, 19 Oct 2026 10:00:00 +0200")
+        );
+        assert_eq!(
+            (stashes[1].index, stashes[1].message.as_str()),
+            (1, "On main: sprites")
+        );
+        assert!(parse_stash_list("").is_empty());
+    }
+
+    #[test]
+    fn stash_list_of_a_repository() {
+        let pth = temp_repo("stash-list");
+        std::fs::write(pth.join("project.json"), "{}").unwrap();
+        run(vec!["add", "."], Some(&pth)).status().unwrap();
+        run(vec!["commit", "-q", "-m", "init"], Some(&pth))
+            .status()
+            .unwrap();
+        assert!(stash_list(&pth).unwrap().is_empty());
+
+        std::fs::write(pth.join("project.json"), "{\"targets\":[]}").unwrap();
+        run(vec!["stash", "push", "-m", "empty project"], Some(&pth))
+            .status()
+            .unwrap();
+        let stashes = stash_list(&pth).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert!(stashes[0].message.ends_with(": empty project"));
+        std::fs::remove_dir_all(&pth).unwrap();
+    }
+
+    #[test]
+    fn parse_porcelain_v2() {
+        let stdout = [
+            "# branch.oid 1234567890abcdef",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "1 .M N... 100644 100644 100644 aaaa bbbb project.json",
+            "1 A. N... 000000 100644 100644 0000 cccc my costume.svg",
+            "2 R. N... 100644 100644 100644 dddd dddd R100 new.wav",
+            "old.wav",
+            "u UU N... 100644 100644 100644 100644 eeee ffff 0000 conflict.png",
+            "? untracked file.mp3",
+            "",
+        ]
+        .join("\0");
+
+        let status = parse_status(&stdout).unwrap();
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+
+        let files: Vec<_> = status
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.orig_path.as_deref(), f.state))
+            .collect();
+        assert_eq!(
+            files,
+            [
+                ("project.json", None, "modified"),
+                ("my costume.svg", None, "added"),
+                ("new.wav", Some("old.wav"), "renamed"),
+                ("conflict.png", None, "conflicted"),
+                ("untracked file.mp3", None, "untracked"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_log_with_multiline_messages() {
+        let stdout = [
+            "aaaa",
+            "Sprite1: +3 blocks",
+            "line one\nline two\n",
+            "Ann",
+            "ann@example.com",
+            "Mon, 1 Jan 2024 10:00:00 +0000\n",
+            "bbbb",
+            "Initial commit",
+            "",
+            "Bo",
+            "bo@example.com",
+            "Sun, 31 Dec 2023 10:00:00 +0000",
+        ]
+        .join("\0");
+
+        let commits = parse_log(&stdout);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].commit, "aaaa");
+        assert_eq!(commits[0].body, "line one\nline two");
+        assert_eq!(commits[1].commit, "bbbb");
+        assert_eq!(commits[1].subject, "Initial commit");
+        assert_eq!(commits[1].author.email, "bo@example.com");
+        assert!(parse_log("").is_empty());
+    }
+
+    #[test]
+    fn log_filters_are_plain_text() {
+        let pth = temp_repo("log");
+        for (message, author) in [
+            ("fix [wip] v1.0*", "A. (Bo) <bo@example.com>"),
+            ("fix wip v100", "Al <al@example.com>"),
+        ] {
+            std::fs::write(pth.join("project.json"), message).unwrap();
+            run(vec!["add", "."], Some(&pth)).status().unwrap();
+            run(
+                vec!["commit", "-q", "-m", message, "--author", author],
+                Some(&pth),
+            )
+            .status()
+            .unwrap();
+        }
+
+        let subjects = |filter: LogFilter| -> Vec<String> {
+            log(&pth, &filter)
+                .unwrap()
+                .into_iter()
+                .map(|commit| commit.subject)
+                .collect()
+        };
+        assert_eq!(
+            subjects(LogFilter {
+                text: Some("[WIP] v1.0*"),
+                ..Default::default()
+            }),
+            vec!["fix [wip] v1.0*"]
+        );
+        assert_eq!(
+            subjects(LogFilter {
+                author: Some("A. (Bo)"),
+                ..Default::default()
+            }),
+            vec!["fix [wip] v1.0*"]
+        );
+        assert!(subjects(LogFilter {
+            author: Some("A.*"),
+            ..Default::default()
+        })
+        .is_empty());
+        std::fs::remove_dir_all(&pth).unwrap();
+    }
+
+    #[test]
+    fn parse_raw_file_changes() {
+        let zero = "0".repeat(40);
+        let (a, b) = ("a".repeat(40), "b".repeat(40));
+        let stdout = format!(
+            "2222\0\n:100644 100644 {a} {b} M\0project.json\0\
+             1111\0\n:000000 100644 {zero} {a} A\0project.json\0"
+        );
+
+        let changes = parse_file_changes(&stdout);
+        assert_eq!(
+            changes["2222"],
+            FileChange {
+                old: Some(a.clone()),
+                new: Some(b)
+            }
+        );
+        assert_eq!(
+            changes["1111"],
+            FileChange {
+                old: None,
+                new: Some(a)
+            }
+        );
+    }
+
+    #[test]
+    fn parse_detached_head() {
+        let status = parse_status("# branch.oid abc\0# branch.head (detached)\0").unwrap();
+        assert_eq!(status.branch, None);
+        assert!(status.files.is_empty());
+    }
+}
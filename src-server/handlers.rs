@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::net::TcpStream;
 use std::{
@@ -15,8 +15,9 @@ use serde_json::{json, Value};
 use tungstenite::{Message, WebSocket};
 use walkdir::WalkDir;
 
-use crate::config::{gh_token, project_config};
-use crate::diff::structs::{AssetChange, AssetChangeType, Diff, ScriptChanges};
+use crate::config::{gh_token, project_config, LayoutChanges, ProjectSettings};
+use crate::diff::scratchblocks::ScratchblocksRenderer;
+use crate::diff::structs::{AssetChange, AssetChangeType, Diff, ScriptChanges, ScriptMoveType};
 use crate::diff::vec_utils::group_costumes;
 use crate::gh_auth;
 use crate::git;
@@ -40,7 +41,6 @@ macro_rules! here {
 }
 
 static CLONE_NAME: Lazy<Regex> = regex_static::lazy_regex!("'(.*)'");
-static AHEAD_COMMITS: Lazy<Regex> = regex_static::lazy_regex!(r#"' by (\d+) commit(?:s)?\."#);
 
 /// Represents all available command types to use with the server
 #[derive(Serialize, Deserialize)]
@@ -66,6 +66,89 @@ enum CmdData<'a> {
         email: String,
     },
     URL(String),
+    LogQuery(LogQuery),
+    Commit {
+        project_name: String,
+        commit: String,
+    },
+    Revisions(RevisionRange),
+    Stash {
+        project_name: String,
+        index: Option<usize>,
+        message: Option<String>,
+    },
+    Settings {
+        project_name: String,
+        settings: ProjectSettings,
+    },
+    Revision {
+        project_name: String,
+        revision: Option<String>,
+        sprite_name: Option<String>,
+    },
+    CommitOptions {
+        project_name: String,
+        /// Make one commit for each changed sprite instead of one for everything
+        #[serde(default)]
+        per_sprite: bool,
+    },
+}
+
+/// Represents filters and pagination for a project's commits
+#[derive(Serialize, Deserialize, Default)]
+struct LogQuery {
+    project_name: String,
+    skip: Option<usize>,
+    limit: Option<usize>,
+    author: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    text: Option<String>,
+    /// Only include commits that changed this sprite
+    sprite: Option<String>,
+}
+
+/// Represents two revisions of a project to compare
+///
+/// A revision is any commit hash, branch name or tag, or `working` for the project on disk.
+/// When left out, `old` is the project before the last save and `new` is the working project
+#[derive(Serialize, Deserialize, Default)]
+struct RevisionRange {
+    project_name: String,
+    old: Option<String>,
+    new: Option<String>,
+    sprite_name: Option<String>,
+}
+
+impl RevisionRange {
+    /// Compare the last save with the working project if only a project was given
+    fn from_data(data: CmdData) -> Option<Self> {
+        match data {
+            CmdData::Project {
+                project_name,
+                sprite_name,
+            } => Some(RevisionRange {
+                project_name: project_name.into(),
+                sprite_name: sprite_name.map(|s| s.into()),
+                ..Default::default()
+            }),
+            CmdData::Revisions(range) => Some(range),
+            _ => None,
+        }
+    }
+
+    /// Load the older and newer project
+    fn projects(&self, pth: &PathBuf) -> Result<(Diff, Diff)> {
+        Ok((
+            load_revision(pth, self.old.as_deref(), "project.old.json")?,
+            load_revision(pth, self.new.as_deref(), "project.json")?,
+        ))
+    }
+
+    /// Whether comparing needs the project from before the last save, which only exists after unzipping
+    fn needs_unzip(&self, pth: &Path) -> bool {
+        self.old.is_none() && !pth.join("project.old.json").exists()
+    }
 }
 
 /// Represents a single command message
@@ -98,13 +181,28 @@ impl CmdHandler<'_> {
     /// Diff two strings
     // ANCHOR[id=diff]
     fn get_diff(&mut self, data: CmdData) -> Result<()> {
-        let CmdData::GitDiff {
-            old_content,
-            new_content,
-            project_name,
-        } = data
-        else {
-            return self.send_json(json!({}));
+        let (project_name, old_content, new_content) = match data {
+            CmdData::GitDiff {
+                old_content,
+                new_content,
+                project_name,
+            } => (project_name, old_content, new_content),
+            CmdData::Revisions(range) => {
+                let Some(sprite_name) = &range.sprite_name else {
+                    return self.send_json(json!({}));
+                };
+                let pth = &project_config()
+                    .lock()
+                    .unwrap()
+                    .project_path(&range.project_name);
+                let (old, new) = range.projects(pth)?;
+                (
+                    range.project_name.clone(),
+                    old.scripts(sprite_name),
+                    new.scripts(sprite_name),
+                )
+            }
+            _ => return self.send_json(json!({})),
         };
 
         let pth = &project_config().lock().unwrap().project_path(&project_name);
@@ -321,6 +419,35 @@ impl CmdHandler<'_> {
         )
     }
 
+    /// Get a project's settings for detecting and committing changes
+    // ANCHOR[id=get-project-settings]
+    fn get_project_settings(&mut self, data: CmdData) -> Result<()> {
+        let CmdData::Project { project_name, .. } = data else {
+            return self.send_json(json!({}));
+        };
+
+        let settings = project_config().lock().unwrap().settings(project_name);
+        self.send_json(json!(settings))
+    }
+
+    /// Replace a project's settings for detecting and committing changes
+    // ANCHOR[id=set-project-settings]
+    fn set_project_settings(&mut self, data: CmdData) -> Result<()> {
+        let CmdData::Settings {
+            project_name,
+            settings,
+        } = data
+        else {
+            return self.send_json(json!({}));
+        };
+
+        project_config()
+            .lock()
+            .unwrap()
+            .set_settings(&project_name, &settings);
+        self.send_json(json!({ "success": true }))
+    }
+
     /// Check if a project exists
     // ANCHOR[id=exists]
     fn exists(&mut self, data: CmdData) -> Result<()> {
@@ -358,6 +485,7 @@ impl CmdHandler<'_> {
 
         let projects = &project_config().lock().unwrap();
         let pth = &projects.project_path(&project_name);
+        let settings = projects.settings(project_name);
         let projects = &projects.projects;
 
         fs::copy(pth.join("project.json"), pth.join("project.old.json"))?;
@@ -371,8 +499,9 @@ impl CmdHandler<'_> {
             ))?,
             target_dir.to_path_buf(),
         )?;
+        let normalized = normalize_project(pth, &settings)?;
 
-        self.send_json(json!({ "status": "success" }))
+        self.send_json(json!({ "status": "success", "normalized": normalized }))
     }
 
     /// Get a sprite's scripts, either old or new
@@ -388,31 +517,16 @@ impl CmdHandler<'_> {
 
         let pth = &project_config().lock().unwrap().project_path(&project_name);
 
-        let old_project = serde_json::from_str::<serde_json::Value>(
-            &fs::read_to_string(
-                pth.join(format!("project{}.json", if old { ".old" } else { "" })),
-            )?
-            .as_str(),
-        )?;
-        let targets = old_project["targets"].as_array().unwrap().iter();
+        let project = Diff::from_json(&fs::read_to_string(
+            pth.join(format!("project{}.json", if old { ".old" } else { "" })),
+        )?)?;
 
-        let blocks = if sprite_name.unwrap() == "Stage (stage)" {
-            targets
-                .filter(|t| t["isStage"].as_bool().unwrap() == true)
-                .map(|t| t["blocks"].as_object().unwrap())
-                .next()
-        } else {
-            targets
-                .filter(|t| {
-                    t["name"].as_str().unwrap() == sprite_name.unwrap()
-                        && !t["isStage"].as_bool().unwrap()
-                })
-                .map(|t| t["blocks"].as_object().unwrap())
-                .next()
+        let Some(sprite_name) = sprite_name else {
+            return self.send_json(json!({}));
         };
 
-        if let Some(bl) = blocks {
-            self.send_json(serde_json::Value::Object(bl.to_owned()))
+        if let Some(target) = project.target(sprite_name) {
+            self.send_json(json!(target.blocks))
         } else {
             self.send_json(json!({}))
         }
@@ -501,14 +615,7 @@ impl CmdHandler<'_> {
                 return self.send_json(json!({"status": "nothing new"}));
             }
 
-            let walkdir = WalkDir::new(&pth);
-            let it = walkdir.into_iter();
-            zipping::zip(
-                &mut it.filter_map(|e| e.ok()),
-                &pth,
-                File::create(Path::new(sb3))?,
-                false,
-            );
+            pack_project(pth, sb3)?;
 
             self.send_json(json!({"status": "success"}))
         } else {
@@ -525,43 +632,43 @@ impl CmdHandler<'_> {
     /// Commit new changes to a project
     // ANCHOR[id=commit]
     fn commit(&mut self, data: CmdData) -> Result<()> {
-        let CmdData::Project { project_name, .. } = data else {
-            return self.send_json(json!({}));
+        let (project_name, per_sprite) = match data {
+            CmdData::Project { project_name, .. } => (project_name.to_string(), false),
+            CmdData::CommitOptions {
+                project_name,
+                per_sprite,
+            } => (project_name, per_sprite),
+            _ => return self.send_json(json!({})),
         };
 
-        let pth = &project_config().lock().unwrap().project_path(&project_name);
+        let (pth, settings) = {
+            let config = project_config().lock().unwrap();
+            (
+                &config.project_path(&project_name),
+                config.settings(&project_name),
+            )
+        };
 
-        let current_diff = Diff::new(&serde_json::from_str::<serde_json::Value>(
-            &fs::read_to_string(pth.join("project.old.json"))?.as_str(),
-        )?);
-        let current_project = serde_json::from_str::<serde_json::Value>(
-            &fs::read_to_string(pth.join("project.json"))?.as_str(),
-        )?;
+        let normalized = normalize_project(pth, &settings)?;
 
-        let new_diff = Diff::new(&current_project);
+        let mut new_diff = Diff::from_json(&fs::read_to_string(pth.join("project.json"))?)?;
 
-        for change in new_diff.assets(&current_diff, None) {
-            let _ = fs::remove_file(pth.join(change.path));
+        if settings.layout_changes == LayoutChanges::Ignore {
+            if let Ok(head) = Diff::from_revision(pth, "HEAD:project.json") {
+                new_diff.restore_layout(&head);
+                fs::write(
+                    pth.join("project.json"),
+                    serde_json::to_string(&new_diff.data)?,
+                )?;
+            }
         }
 
-        // remove all assets that aren't used in the json
-        let project_assets = get_assets(serde_json::from_value(current_project)?);
-        let unused_assets = fs::read_dir(pth)?
-            .map(|res| res.unwrap().path())
-            .filter(|path| {
-                let ext = path.extension();
-                if ext.is_none() {
-                    return false;
-                };
-                let ext = ext.unwrap();
-                ext == "svg" || ext == "png" || ext == "mp3" || ext == "wav"
-            })
-            .filter(|path| {
-                !project_assets.contains(&path.file_name().unwrap().to_str().unwrap().to_string())
-            });
+        remove_unused_assets(pth, new_diff.data.clone())?;
 
-        for asset in unused_assets {
-            let _ = fs::remove_file(asset);
+        if per_sprite {
+            if let Ok(head) = Diff::from_revision(pth, "HEAD:project.json") {
+                return self.commit_per_sprite(pth, &head, &new_diff, &settings, &normalized);
+            }
         }
 
         if !git::run(vec!["add", "."], Some(&pth)).status()?.success() {
@@ -583,108 +690,272 @@ impl CmdHandler<'_> {
         }
 
         let previous_revision = Diff::from_revision(&pth, "HEAD~1:project.json")?;
-        let commit_message = previous_revision.commits(&pth, &new_diff)?.join(", ");
+        let (commit_message, commit_body) =
+            previous_revision.commit_message(pth, &new_diff, &settings)?;
 
-        let commit =
-            git::run(vec!["commit", "--amend", "-m", &commit_message], Some(&pth)).output()?;
+        let mut args = vec!["commit", "--amend", "-m", &commit_message];
+        if !commit_body.is_empty() {
+            args.extend(["-m", &commit_body]);
+        }
+        let commit = git::run(args, Some(pth)).output()?;
 
         if !commit.status.success() {
             // TODO: make this less generic
             return self.send_json(json!({ "message": -4 }));
         }
 
-        self.send_json(json!({ "message": commit_message }))
+        self.send_json(json!({
+            "message": commit_message,
+            "body": commit_body,
+            "normalized": normalized
+        }))
     }
 
-    /// Get a project's commits
+    /// Commit project-level changes and then each changed sprite on its own, with only that
+    /// part of project.json and its assets in each commit
+    fn commit_per_sprite(
+        &mut self,
+        pth: &PathBuf,
+        head: &Diff,
+        new: &Diff,
+        settings: &ProjectSettings,
+        normalized: &[String],
+    ) -> Result<()> {
+        let project = fs::read_to_string(pth.join("project.json"))?;
+        let result = commit_steps(pth, head, new, settings);
+        // the steps were written over the project, so put it back whether or not they worked
+        fs::write(pth.join("project.json"), project)?;
+        let mut commits = match result? {
+            Ok(commits) => commits,
+            Err(code) => return self.send_json(json!({ "message": code })),
+        };
+
+        // anything the steps missed, like sprites changing order, still needs committing
+        if !git::run(vec!["add", "."], Some(pth)).status()?.success() {
+            return self.send_json(json!({ "message": -1 }));
+        }
+        let staged = git::run(vec!["diff", "--cached", "--quiet"], Some(pth)).status()?;
+        if !staged.success() {
+            let previous = Diff::from_revision(pth, "HEAD:project.json")?;
+            let (subject, body) = previous.commit_message(pth, new, settings)?;
+            if git_commit(pth, &subject, &body)?.is_err() {
+                return self.send_json(json!({ "message": -4 }));
+            }
+            commits.push(json!({ "sprite": null, "message": subject, "body": body }));
+        }
+
+        if commits.is_empty() {
+            return self.send_json(json!({ "message": -3 }));
+        }
+        let message = commits
+            .iter()
+            .filter_map(|commit| commit["message"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.send_json(json!({
+            "message": message,
+            "commits": commits,
+            "normalized": normalized
+        }))
+    }
+
+    /// Get a project's commits, optionally filtered and paginated
     // ANCHOR[id=get-commits]
     fn get_commits(&mut self, data: CmdData) -> Result<()> {
-        let CmdData::Project { project_name, .. } = data else {
-            return self.send_json(json!({}));
+        let LogQuery {
+            project_name,
+            skip,
+            limit,
+            author,
+            since,
+            until,
+            text,
+            sprite,
+        } = match data {
+            CmdData::Project { project_name, .. } => LogQuery {
+                project_name: project_name.into(),
+                ..Default::default()
+            },
+            CmdData::LogQuery(query) => query,
+            _ => return self.send_json(json!({})),
         };
         let pth = &project_config().lock().unwrap().project_path(&project_name);
-        let format = "--pretty=format:{\"commit\": \"%H\", \"subject\": \"%s\", \"body\": \"%b\", \"author\": {\"name\": \"%aN\", \"email\": \"%aE\", \"date\": \"%aD\"}},";
 
-        let git_log = String::from_utf8(
-            git::run(vec!["log", format], Some(pth))
-                .output()
-                .context(here!(""))?
-                .stdout,
-        )?;
+        let mut filter = git::LogFilter {
+            skip,
+            limit,
+            author: author.as_deref(),
+            since: since.as_deref(),
+            until: until.as_deref(),
+            text: text.as_deref(),
+            path: None,
+        };
 
-        let binding = if git_log.as_str().matches("\"commit\"").count() > 1 {
-            format!(
-                "[{}]",
-                git_log.replace(" }\n}", " }\n},").as_str()[..git_log.len() - 1].to_string()
-            )
-        } else {
-            format!("[{git_log}]")
+        let Some(sprite) = sprite else {
+            return self.send_json(json!(
+                git::log(pth, &filter).context(here!("failed to read git log"))?
+            ));
         };
-        let log_output = if binding.ends_with(",]") {
-            binding[..binding.len() - 2].to_owned() + "]"
-        } else {
-            binding
+
+        // which sprites a commit touched isn't known to git, so pagination happens after filtering
+        filter.skip = None;
+        filter.limit = None;
+        filter.path = Some("project.json");
+
+        let changes = git::file_changes(pth, "project.json")
+            .context(here!("failed to read project history"))?;
+        // neighbouring commits share a blob, so each version is only read once
+        let mut projects: HashMap<String, Option<ProjectData>> = HashMap::new();
+        let mut project = |blob: &str| {
+            projects
+                .entry(blob.to_string())
+                .or_insert_with(|| {
+                    git::show_revision(pth, blob)
+                        .ok()
+                        .and_then(|json| ProjectData::from_json(&json).ok())
+                })
+                .clone()
         };
 
-        self.send_json(serde_json::from_str(&log_output)?)
+        let mut commits = vec![];
+        for commit in git::log(pth, &filter).context(here!("failed to read git log"))? {
+            let Some(change) = changes.get(&commit.commit) else {
+                continue;
+            };
+            let new = change.new.as_deref().and_then(&mut project);
+            let changed = match change.old.as_deref() {
+                Some(old) => changed_targets(project(old).as_ref(), new.as_ref()),
+                // a commit that created the project only changed the sprites it added
+                None => new
+                    .iter()
+                    .flat_map(|project| &project.targets)
+                    .map(|target| target.display_name())
+                    .collect(),
+            };
+            if changed
+                .iter()
+                .any(|name| *name == sprite || *name == format!("{sprite} (stage)"))
+            {
+                commits.push(commit);
+            }
+        }
+        let commits: Vec<_> = commits
+            .into_iter()
+            .skip(skip.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+
+        self.send_json(json!(commits))
     }
 
     // ANCHOR[id=get-changed-sprites]
     fn get_changed_sprites(&mut self, data: CmdData) -> Result<()> {
-        let CmdData::Project { project_name, .. } = data else {
+        let Some(range) = RevisionRange::from_data(data) else {
             return self.send_json(json!({}));
         };
 
-        let pth = &project_config().lock().unwrap().project_path(&project_name);
+        let pth = &project_config()
+            .lock()
+            .unwrap()
+            .project_path(&range.project_name);
 
-        let binding = &fs::read_to_string(pth.join("project.old.json"));
-        let project_old_json = match binding {
-            Ok(fh) => fh.as_str(),
-            Err(_) => {
-                return self
-                    .send_json(json!({ "status": "unzip the project first that should do it" }));
-            }
+        if range.needs_unzip(pth) {
+            return self
+                .send_json(json!({ "status": "unzip the project first that should do it" }));
+        }
+
+        let (current_diff, new_diff) = range.projects(pth)?;
+
+        let scripts = current_diff.blocks(pth, &new_diff)?;
+        let sprites = changed_sprites(&current_diff, &new_diff, &scripts)?;
+        let layout = current_diff.layout_changes(&new_diff);
+        self.send_json(json!({ "sprites": sprites, "layout": layout }))
+    }
+
+    /// Get every script that was added, removed or edited, named by its hat block
+    // ANCHOR[id=get-changed-scripts]
+    fn get_changed_scripts(&mut self, data: CmdData) -> Result<()> {
+        let Some(range) = RevisionRange::from_data(data) else {
+            return self.send_json(json!({}));
         };
-        let _current_project = serde_json::from_str::<serde_json::Value>(project_old_json)?;
 
-        let current_diff = Diff::new(&_current_project);
-        let _new_project = serde_json::from_str::<serde_json::Value>(
-            &fs::read_to_string(pth.join("project.json"))?.as_str(),
-        )?;
+        let pth = &project_config()
+            .lock()
+            .unwrap()
+            .project_path(&range.project_name);
 
-        let new_diff = Diff::new(&_new_project);
+        if range.needs_unzip(pth) {
+            return self
+                .send_json(json!({ "status": "unzip the project first that should do it" }));
+        }
 
-        let mut sprites: Vec<_> = current_diff
-            .blocks(&pth, &new_diff)?
-            .into_iter()
-            .map(|ScriptChanges { sprite, .. }| {
-                let parts = sprite.split(" ").collect::<Vec<_>>();
-                if parts[0] == "Stage" && parts[1..].join("") == "(stage)" {
-                    (parts[0].to_string(), true)
-                } else {
-                    (sprite, false)
-                }
-            })
-            .collect();
+        let (current_diff, new_diff) = range.projects(pth)?;
 
-        sprites.extend(
-            [
-                new_diff.assets(&current_diff, None),
-                current_diff.assets(&new_diff, None),
-            ]
-            .concat()
-            .into_iter()
-            .map(|AssetChange { sprite, .. }| {
-                let parts = sprite.split(" ").collect::<Vec<_>>();
-                if parts[0] == "Stage" && parts[1..].join("") == "(stage)" {
-                    (parts[0].to_string(), true)
-                } else {
-                    (sprite, false)
-                }
-            }),
-        );
+        let mut scripts = current_diff.script_changes(&new_diff);
+        if let Some(sprite_name) = &range.sprite_name {
+            scripts.retain(|change| &change.sprite == sprite_name);
+        }
+        self.send_json(json!({ "scripts": scripts }))
+    }
+
+    /// Get the IDs of blocks in a sprite that were added, removed, modified or moved, so the
+    /// editor can highlight them
+    // ANCHOR[id=block-diff]
+    fn block_diff(&mut self, data: CmdData) -> Result<()> {
+        let Some(range) = RevisionRange::from_data(data) else {
+            return self.send_json(json!({}));
+        };
+        let Some(sprite_name) = &range.sprite_name else {
+            return self.send_json(json!({}));
+        };
+
+        let pth = &project_config()
+            .lock()
+            .unwrap()
+            .project_path(&range.project_name);
 
-        self.send_json(json!({ "sprites": sprites.iter().collect::<HashSet<_>>() }))
+        if range.needs_unzip(pth) {
+            return self
+                .send_json(json!({ "status": "unzip the project first that should do it" }));
+        }
+
+        let (current_diff, new_diff) = range.projects(pth)?;
+        self.send_json(json!(current_diff.block_diff(&new_diff, sprite_name)))
+    }
+
+    /// Render the scripts of every sprite, or only one, as scratchblocks at any revision
+    // ANCHOR[id=get-scratchblocks]
+    fn get_scratchblocks(&mut self, data: CmdData) -> Result<()> {
+        let (project_name, revision, sprite_name) = match data {
+            CmdData::Project {
+                project_name,
+                sprite_name,
+            } => (
+                project_name.to_string(),
+                None,
+                sprite_name.map(String::from),
+            ),
+            CmdData::Revision {
+                project_name,
+                revision,
+                sprite_name,
+            } => (project_name, revision, sprite_name),
+            _ => return self.send_json(json!({})),
+        };
+
+        let pth = &project_config().lock().unwrap().project_path(&project_name);
+        let project = load_revision(pth, revision.as_deref(), "project.json")?;
+
+        let mut sprites = serde_json::Map::new();
+        for target in &project.data.targets {
+            let name = target.display_name();
+            if sprite_name.as_ref().is_some_and(|sprite| sprite != &name) {
+                continue;
+            }
+            let scripts = ScratchblocksRenderer::new(target).render();
+            sprites.insert(name, json!(scripts));
+        }
+        self.send_json(json!({ "sprites": sprites }))
     }
 
     /// Set up GitHub authentication for use with any configured project
@@ -796,68 +1067,225 @@ impl CmdHandler<'_> {
 
     // ANCHOR[id=get-changed-assets]
     fn get_changed_assets(&mut self, data: CmdData) -> Result<()> {
+        let Some(range) = RevisionRange::from_data(data) else {
+            return self.send_json(json!({}));
+        };
+
+        let pth = &project_config()
+            .lock()
+            .unwrap()
+            .project_path(&range.project_name);
+
+        if range.needs_unzip(pth) {
+            return self.send_json(json!({ "status": -2 }));
+        }
+
+        let (current_diff, new_diff) = range.projects(pth)?;
+
+        let Some(costume_changes) = changed_assets(
+            &current_diff,
+            &new_diff,
+            |path| read_asset(pth, range.old.as_deref(), path),
+            |path| read_asset(pth, range.new.as_deref(), path),
+        ) else {
+            return self.send_json(json!({ "status": -1 }));
+        };
+
+        self.send_json(json!({"status": 0, "data": costume_changes}))
+    }
+
+    /// Get the branch state of a project and which sprites and assets each changed file belongs to
+    // ANCHOR[id=repo-status]
+    fn repo_status(&mut self, data: CmdData) -> Result<()> {
         let CmdData::Project { project_name, .. } = data else {
             return self.send_json(json!({}));
         };
 
         let pth = &project_config().lock().unwrap().project_path(&project_name);
 
-        let binding = &fs::read_to_string(pth.join("project.old.json"));
-        let project_old_json = match binding {
-            Ok(fh) => fh.as_str(),
-            Err(_) => {
-                return self.send_json(json!({ "status": -2 }));
-            }
+        let status = git::status(pth).context(here!("failed to get repository status"))?;
+
+        let current_project = fs::read_to_string(pth.join("project.json"))
+            .ok()
+            .and_then(|json| ProjectData::from_json(&json).ok());
+        let previous_project = git::show_revision(pth, "HEAD:project.json")
+            .ok()
+            .and_then(|json| ProjectData::from_json(&json).ok());
+
+        let owners_of = |project: &Option<ProjectData>| {
+            project
+                .as_ref()
+                .map(|p| p.asset_owners())
+                .unwrap_or_default()
         };
-        let _current_project = serde_json::from_str::<serde_json::Value>(project_old_json)?;
+        let current_owners = owners_of(&current_project);
+        let previous_owners = owners_of(&previous_project);
+
+        let files: Vec<Value> = status
+            .files
+            .iter()
+            .map(|file| {
+                let mut assets = current_owners.get(&file.path).cloned().unwrap_or_default();
+                for owner in previous_owners.get(&file.path).into_iter().flatten() {
+                    if !assets.contains(owner) {
+                        assets.push(owner.clone());
+                    }
+                }
 
-        let current_diff = Diff::new(&_current_project);
-        let _new_project = serde_json::from_str::<serde_json::Value>(
-            &fs::read_to_string(pth.join("project.json"))?.as_str(),
-        )?;
+                let sprites = if file.path == "project.json" {
+                    changed_targets(previous_project.as_ref(), current_project.as_ref())
+                } else {
+                    let mut sprites: Vec<String> = assets
+                        .iter()
+                        .map(|owner| match owner.on_stage {
+                            true => format!("{} (stage)", owner.sprite),
+                            false => owner.sprite.clone(),
+                        })
+                        .collect();
+                    sprites.sort();
+                    sprites.dedup();
+                    sprites
+                };
+
+                json!({
+                    "path": file.path,
+                    "orig_path": file.orig_path,
+                    "code": file.code,
+                    "state": file.state,
+                    "assets": assets,
+                    "sprites": sprites,
+                })
+            })
+            .collect();
+
+        self.send_json(json!({
+            "status": if status.files.is_empty() { 1 } else { 2 },
+            "commits_ahead": status.ahead,
+            "branch": status.branch,
+            "upstream": status.upstream,
+            "ahead": status.ahead,
+            "behind": status.behind,
+            "in_progress": status.in_progress,
+            "files": files,
+        }))
+    }
+
+    /// Get the sprites, scripts and assets a past commit changed compared to its parent
+    // ANCHOR[id=commit-details]
+    fn commit_details(&mut self, data: CmdData) -> Result<()> {
+        let CmdData::Commit {
+            project_name,
+            commit,
+        } = data
+        else {
+            return self.send_json(json!({}));
+        };
 
-        let new_diff = Diff::new(&_new_project);
+        let pth = &project_config().lock().unwrap().project_path(&project_name);
 
-        let mut costume_changes = current_diff.assets(&new_diff, Some(AssetChangeType::After));
-        let newer_changes = new_diff.assets(&current_diff, Some(AssetChangeType::Before));
+        let new_diff = Diff::from_revision(pth, &format!("{commit}:project.json"))
+            .context(here!("failed to read project at commit"))?;
+        // the first commit has no parent to compare against
+        let current_diff = Diff::from_revision(pth, &format!("{commit}~1:project.json"))
+            .unwrap_or_else(|_| Diff::new(ProjectData::default()));
+
+        let scripts = current_diff.blocks(pth, &new_diff)?;
+        let sprites = changed_sprites(&current_diff, &new_diff, &scripts)?;
+
+        let parent = format!("{commit}~1");
+        let Some(assets) = changed_assets(
+            &current_diff,
+            &new_diff,
+            |path| git::show_file(pth, &parent, path).ok(),
+            |path| git::show_file(pth, &commit, path).ok(),
+        ) else {
+            return self.send_json(json!({ "status": -1 }));
+        };
 
-        costume_changes.extend(newer_changes);
+        self.send_json(json!({
+            "status": 0,
+            "commit": commit,
+            "sprites": sprites,
+            "scripts": scripts,
+            "assets": assets,
+        }))
+    }
 
-        for change in &mut costume_changes {
-            if !pth.join(change.path.clone()).exists() {
-                return self.send_json(json!({ "status": -1 }));
-            }
-            change.contents = Some(fs::read(pth.join(change.path.clone()))?.into());
-        }
+    /// Stash uncommitted changes to a project and restore its SB3 to the last commit
+    // ANCHOR[id=stash-save]
+    fn stash_save(&mut self, data: CmdData) -> Result<()> {
+        let CmdData::Stash {
+            project_name,
+            message,
+            ..
+        } = data
+        else {
+            return self.send_json(json!({}));
+        };
 
-        let costume_changes = group_costumes(costume_changes);
+        let (pth, sb3) = {
+            let config = project_config().lock().unwrap();
+            (
+                config.project_path(&project_name),
+                config.project_sb3(&project_name),
+            )
+        };
 
-        self.send_json(json!({"status": 0, "data": costume_changes}))
+        self.send_json(save_stash(&pth, &sb3, message.as_deref())?)
     }
 
-    // ANCHOR[id=repo-status]
-    fn repo_status(&mut self, data: CmdData) -> Result<()> {
-        let CmdData::Project { project_name, .. } = data else {
+    /// List a project's stashed changes
+    // ANCHOR[id=stash-list]
+    fn stash_list(&mut self, data: CmdData) -> Result<()> {
+        let CmdData::Stash { project_name, .. } = data else {
             return self.send_json(json!({}));
         };
 
         let pth = &project_config().lock().unwrap().project_path(&project_name);
 
-        let status = String::from_utf8(git::run(vec!["status"], Some(pth)).output()?.stdout)?;
+        self.send_json(json!(
+            git::stash_list(pth).context(here!("failed to list stashes"))?
+        ))
+    }
 
-        let commits_ahead = if let Some(group) = AHEAD_COMMITS.captures(&status).map(|c| c.get(1)) {
-            group.unwrap().as_str().parse::<usize>().unwrap()
-        } else {
-            0
+    /// Apply stashed changes to a project and repack its SB3, reporting conflicting sprites if it can't be done cleanly
+    // ANCHOR[id=stash-apply]
+    fn stash_apply(&mut self, data: CmdData) -> Result<()> {
+        let CmdData::Stash {
+            project_name,
+            index,
+            ..
+        } = data
+        else {
+            return self.send_json(json!({}));
         };
 
-        if status.contains("nothing to commit") {
-            self.send_json(json!({"status": 1, "commits_ahead": commits_ahead}))
-        } else if status.contains("no changes added to commit") {
-            self.send_json(json!({"status": 2, "commits_ahead": commits_ahead}))
-        } else {
-            self.send_json(json!({"status": 2, "commits_ahead": commits_ahead}))
-        }
+        let (pth, sb3) = {
+            let config = project_config().lock().unwrap();
+            (
+                config.project_path(&project_name),
+                config.project_sb3(&project_name),
+            )
+        };
+
+        self.send_json(apply_stash(&pth, &sb3, index.unwrap_or(0))?)
+    }
+
+    /// Delete a stash entry from a project
+    // ANCHOR[id=stash-drop]
+    fn stash_drop(&mut self, data: CmdData) -> Result<()> {
+        let CmdData::Stash {
+            project_name,
+            index,
+            ..
+        } = data
+        else {
+            return self.send_json(json!({}));
+        };
+
+        let pth = &project_config().lock().unwrap().project_path(&project_name);
+
+        self.send_json(drop_stash(pth, index.unwrap_or(0))?)
     }
 
     fn uninstall(&mut self) -> Result<()> {
@@ -871,6 +1299,358 @@ impl CmdHandler<'_> {
     }
 }
 
+/// Return the names of targets that differ between two projects, with the stage marked as `(stage)`
+fn changed_targets(old: Option<&ProjectData>, new: Option<&ProjectData>) -> Vec<String> {
+    let targets =
+        |project: Option<&ProjectData>| project.map(|p| p.targets.clone()).unwrap_or_default();
+
+    let (old, new) = (targets(old), targets(new));
+    let mut changed: Vec<String> = new
+        .iter()
+        .filter(|target| !old.contains(target))
+        .chain(old.iter().filter(|target| !new.contains(target)))
+        .map(|target| target.display_name())
+        .collect();
+    changed.sort();
+    changed.dedup();
+    changed
+}
+
+/// Reset state that changes while the working project runs to its last committed value,
+/// following the project's normalization rules, and return what was reset
+fn normalize_project(pth: &PathBuf, settings: &ProjectSettings) -> Result<Vec<String>> {
+    let json = fs::read_to_string(pth.join("project.json"))?;
+    let mut project = Diff::from_json(&json)?;
+    let head = Diff::from_revision(pth, "HEAD:project.json")
+        .unwrap_or_else(|_| Diff::new(ProjectData::default()));
+
+    let reset = project.normalize(&head, &settings.normalize);
+
+    let normalized = serde_json::to_string(&project.data)?;
+    if normalized != json {
+        fs::write(pth.join("project.json"), normalized)?;
+    }
+    Ok(reset)
+}
+
+/// Stash a project's uncommitted changes and repack its SB3 from the clean project
+fn save_stash(pth: &PathBuf, sb3: &str, message: Option<&str>) -> Result<Value> {
+    let mut args = vec!["stash", "push", "--include-untracked"];
+    if let Some(message) = message {
+        args.extend(["-m", message]);
+    }
+
+    let stash = git::run(args, Some(pth)).output().context(here!(""))?;
+    if !stash.status.success() {
+        return Ok(json!({ "status": "fail" }));
+    }
+    if String::from_utf8(stash.stdout)?.contains("No local changes to save") {
+        return Ok(json!({ "status": "nothing to stash" }));
+    }
+
+    // the clean project is now the baseline for changes
+    fs::copy(pth.join("project.json"), pth.join("project.old.json"))?;
+    pack_project(pth, sb3)?;
+
+    Ok(json!({ "status": "success" }))
+}
+
+/// Apply a stash to a project and repack its SB3, reporting sprites changed both in the stash
+/// and since it was made
+fn apply_stash(pth: &PathBuf, sb3: &str, index: usize) -> Result<Value> {
+    if index >= git::stash_list(pth)?.len() {
+        return Ok(json!({ "status": "no such stash" }));
+    }
+    let stash = format!("stash@{{{index}}}");
+
+    let revision = |rev: &str| {
+        git::show_revision(pth, &format!("{rev}:project.json"))
+            .ok()
+            .and_then(|json| ProjectData::from_json(&json).ok())
+    };
+    let base = revision(&format!("{stash}^1"));
+    let stashed = revision(&stash);
+    let current = fs::read_to_string(pth.join("project.json"))
+        .ok()
+        .and_then(|json| ProjectData::from_json(&json).ok());
+
+    // sprites changed both in the stash and since the stash was made
+    let stash_changes = changed_targets(base.as_ref(), stashed.as_ref());
+    let conflicts: Vec<_> = changed_targets(base.as_ref(), current.as_ref())
+        .into_iter()
+        .filter(|sprite| stash_changes.contains(sprite))
+        .collect();
+
+    if !git::status(pth)?.files.is_empty() {
+        return Ok(json!({ "status": "uncommitted changes", "conflicts": conflicts }));
+    }
+
+    let apply = git::run(vec!["stash", "apply", &stash], Some(pth))
+        .output()
+        .context(here!(""))?;
+
+    if !apply.status.success() {
+        // the working tree was clean beforehand, so nothing but the failed apply is discarded
+        git::run(vec!["reset", "--hard", "HEAD"], Some(pth))
+            .output()
+            .context(here!(""))?;
+        // files the stash added are still around after resetting tracked ones
+        let created: Vec<_> = git::status(pth)?
+            .files
+            .into_iter()
+            .filter(|file| file.state == "untracked")
+            .map(|file| file.path)
+            .collect();
+        if !created.is_empty() {
+            let mut args = vec!["clean", "-f", "--"];
+            args.extend(created.iter().map(|path| path.as_str()));
+            git::run(args, Some(pth)).output().context(here!(""))?;
+        }
+        return Ok(json!({ "status": "conflict", "conflicts": conflicts }));
+    }
+
+    // changes from the stash should show up as uncommitted again
+    fs::write(
+        pth.join("project.old.json"),
+        git::show_file(pth, "HEAD", "project.json")?,
+    )?;
+    pack_project(pth, sb3)?;
+
+    Ok(json!({ "status": "success" }))
+}
+
+/// Delete a stash entry, checking that it exists first
+fn drop_stash(pth: &PathBuf, index: usize) -> Result<Value> {
+    if index >= git::stash_list(pth)?.len() {
+        return Ok(json!({ "success": false, "status": "no such stash" }));
+    }
+    let stash = format!("stash@{{{index}}}");
+
+    let drop = git::run(vec!["stash", "drop", &stash], Some(pth))
+        .status()
+        .context(here!(""))?;
+
+    Ok(json!({ "success": drop.success() }))
+}
+
+/// Zip a project directory back into its SB3
+fn pack_project(pth: &PathBuf, sb3: &str) -> Result<()> {
+    // the folder itself has no name inside the SB3, and the repository doesn't belong in it
+    let walkdir = WalkDir::new(pth).min_depth(1);
+    let it = walkdir
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git");
+    zipping::zip(
+        &mut it.filter_map(|e| e.ok()),
+        pth,
+        File::create(Path::new(sb3))?,
+        false,
+    );
+    Ok(())
+}
+
+/// Load a project at a revision, or from `default` in the project directory if no revision is given
+fn load_revision(pth: &PathBuf, revision: Option<&str>, default: &str) -> Result<Diff> {
+    let file = match revision {
+        Some("working") => "project.json",
+        None => default,
+        Some(revision) => {
+            return Diff::from_revision(pth, &format!("{revision}:project.json"))
+                .context(here!("failed to read project at revision"))
+        }
+    };
+
+    Diff::from_json(
+        &fs::read_to_string(pth.join(file)).context(here!("failed to read project file"))?,
+    )
+}
+
+/// Read an asset file at a revision, or from the project directory if no revision is given
+fn read_asset(pth: &PathBuf, revision: Option<&str>, path: &str) -> Option<Vec<u8>> {
+    match revision {
+        None | Some("working") => fs::read(pth.join(path)).ok(),
+        Some(revision) => git::show_file(pth, revision, path).ok(),
+    }
+}
+
+/// Return every sprite with changed scripts or assets between two projects, as its name and whether it's the stage
+///
+/// `scripts` are the script changes from [`Diff::blocks`], which are passed in so callers that also
+/// return them only diff the scripts once
+fn changed_sprites(
+    old: &Diff,
+    new: &Diff,
+    scripts: &[ScriptChanges],
+) -> Result<HashSet<(String, bool)>> {
+    let split_stage = |sprite: String| {
+        let parts = sprite.split(' ').collect::<Vec<_>>();
+        if parts[0] == "Stage" && parts[1..].join("") == "(stage)" {
+            (parts[0].to_string(), true)
+        } else {
+            (sprite, false)
+        }
+    };
+
+    let mut sprites: HashSet<_> = scripts
+        .iter()
+        .map(|ScriptChanges { sprite, .. }| split_stage(sprite.clone()))
+        .collect();
+
+    sprites.extend(
+        [new.assets(old, None), old.assets(new, None)]
+            .concat()
+            .into_iter()
+            .map(|AssetChange { sprite, .. }| split_stage(sprite)),
+    );
+
+    sprites.extend(
+        old.target_changes(new)
+            .into_iter()
+            .map(|change| split_stage(change.sprite)),
+    );
+
+    sprites.extend(
+        old.property_changes(new)
+            .into_iter()
+            .map(|change| split_stage(change.sprite)),
+    );
+
+    for change in old.script_moves(new) {
+        if change.action == ScriptMoveType::Move {
+            sprites.insert(split_stage(change.from_sprite));
+        }
+        sprites.insert(split_stage(change.sprite));
+    }
+
+    sprites.extend(
+        old.comment_changes(new)
+            .into_iter()
+            .map(|change| split_stage(change.sprite)),
+    );
+
+    Ok(sprites)
+}
+
+/// Commit a message, returning the command's error output if it failed
+fn git_commit(pth: &PathBuf, subject: &str, body: &str) -> Result<Result<(), String>> {
+    let mut args = vec!["commit", "-m", subject];
+    if !body.is_empty() {
+        args.extend(["-m", body]);
+    }
+    let commit = git::run(args, Some(pth)).output()?;
+    if commit.status.success() {
+        Ok(Ok(()))
+    } else {
+        Ok(Err(String::from_utf8(commit.stderr)?))
+    }
+}
+
+/// Commit each step of [`Diff::split_by_sprite`] by writing it as project.json and staging it
+/// with the assets it added or removed
+///
+/// Returns the commits that were made, or the error code for the commit command
+/// Remove all assets in the project folder that aren't used in the json
+fn remove_unused_assets(pth: &Path, project: ProjectData) -> Result<()> {
+    let project_assets = get_assets(project);
+    let unused_assets = fs::read_dir(pth)?
+        .map(|res| res.unwrap().path())
+        .filter(|path| {
+            let ext = path.extension();
+            if ext.is_none() {
+                return false;
+            };
+            let ext = ext.unwrap();
+            ext == "svg" || ext == "png" || ext == "mp3" || ext == "wav"
+        })
+        .filter(|path| {
+            !project_assets.contains(&path.file_name().unwrap().to_str().unwrap().to_string())
+        });
+
+    for asset in unused_assets {
+        let _ = fs::remove_file(asset);
+    }
+    Ok(())
+}
+
+fn commit_steps(
+    pth: &PathBuf,
+    head: &Diff,
+    new: &Diff,
+    settings: &ProjectSettings,
+) -> Result<Result<Vec<serde_json::Value>, i32>> {
+    let mut commits = vec![];
+    let mut previous = Diff::new(head.data.clone());
+
+    for (sprite, step) in head.split_by_sprite(new) {
+        fs::write(pth.join("project.json"), serde_json::to_string(&step.data)?)?;
+
+        let old_assets = get_assets(previous.data.clone());
+        let new_assets = get_assets(step.data.clone());
+        let mut added: Vec<&str> = vec!["project.json"];
+        added.extend(
+            new_assets
+                .iter()
+                .filter(|asset| !old_assets.contains(asset))
+                .map(|asset| asset.as_str()),
+        );
+        // assets can still be on disk when a later step uses them, so unstage them directly
+        let removed: Vec<&str> = old_assets
+            .iter()
+            .filter(|asset| !new_assets.contains(asset))
+            .map(|asset| asset.as_str())
+            .collect();
+
+        let mut commands = vec![[vec!["add", "--"], added].concat()];
+        if !removed.is_empty() {
+            let remove = vec!["rm", "--cached", "--quiet", "--ignore-unmatch", "--"];
+            commands.push([remove, removed].concat());
+        }
+        for args in commands {
+            if !git::run(args, Some(pth)).status()?.success() {
+                return Ok(Err(-1));
+            }
+        }
+
+        let (subject, body) = previous.commit_message(pth, &step, settings)?;
+        if let Err(stderr) = git_commit(pth, &subject, &body)? {
+            if stderr.contains("git config --global user.email") {
+                return Ok(Err(-2));
+            }
+            return Ok(Err(-4));
+        }
+        commits.push(json!({ "sprite": sprite, "message": subject, "body": body }));
+        previous = step;
+    }
+
+    Ok(Ok(commits))
+}
+
+/// Return added and removed assets between two projects grouped by sprite and asset name
+///
+/// Contents of removed assets are read with `read_old` and added assets with `read_new`.
+/// Returns `None` if any asset file couldn't be read
+fn changed_assets(
+    old: &Diff,
+    new: &Diff,
+    read_old: impl Fn(&str) -> Option<Vec<u8>>,
+    read_new: impl Fn(&str) -> Option<Vec<u8>>,
+) -> Option<HashMap<String, HashMap<String, Vec<AssetChange>>>> {
+    let mut costume_changes = old.assets(new, Some(AssetChangeType::After));
+    let newer_changes = new.assets(old, Some(AssetChangeType::Before));
+
+    costume_changes.extend(newer_changes);
+
+    for change in &mut costume_changes {
+        let contents = match change.kind {
+            Some(AssetChangeType::Before) => read_old(&change.path),
+            _ => read_new(&change.path),
+        };
+        change.contents = Some(contents?.into());
+    }
+
+    Some(group_costumes(costume_changes))
+}
+
 pub fn handle_command(msg: Cmd, socket: &mut WebSocket<TcpStream>, debug: bool) -> Result<()> {
     let mut handler = CmdHandler::new(debug, socket);
 
@@ -887,6 +1667,8 @@ pub fn handle_command(msg: Cmd, socket: &mut WebSocket<TcpStream>, debug: bool)
         // project-specific
         "set-project-details" => handler.set_project_details(msg.data, false),
         "get-project-details" => handler.get_project_details(msg.data),
+        "get-project-settings" => handler.get_project_settings(msg.data),
+        "set-project-settings" => handler.set_project_settings(msg.data),
         "unzip" => handler.unzip(msg.data),
         "commit" => handler.commit(msg.data),
         "push" => handler.push(msg.data),
@@ -895,9 +1677,93 @@ pub fn handle_command(msg: Cmd, socket: &mut WebSocket<TcpStream>, debug: bool)
         "previous-project" => handler.get_sprite_scripts(msg.data, true),
         "get-commits" => handler.get_commits(msg.data),
         "get-changed-sprites" => handler.get_changed_sprites(msg.data),
+        "get-changed-scripts" => handler.get_changed_scripts(msg.data),
+        "get-scratchblocks" => handler.get_scratchblocks(msg.data),
+        "block-diff" => handler.block_diff(msg.data),
         "get-changed-assets" => handler.get_changed_assets(msg.data),
         "repo-status" => handler.repo_status(msg.data),
+        "commit-details" => handler.commit_details(msg.data),
+        "stash-save" => handler.stash_save(msg.data),
+        "stash-list" => handler.stash_list(msg.data),
+        "stash-apply" => handler.stash_apply(msg.data),
+        "stash-drop" => handler.stash_drop(msg.data),
 
         _ => unreachable!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_settings_keep_the_asset_file() {
+        let pth = std::env::temp_dir().join(format!("scratch-git-assets-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&pth);
+        fs::create_dir_all(&pth).unwrap();
+        fs::write(pth.join("cat.svg"), "<svg/>").unwrap();
+        fs::write(pth.join("dog.svg"), "<svg/>").unwrap();
+
+        let json = r#"{"targets":[{"isStage":false,"name":"Cat","costumes":[{"assetId":"cat","name":"costume1","dataFormat":"svg","rotationCenterX":0,"rotationCenterY":0}]}]}"#;
+        remove_unused_assets(&pth, ProjectData::from_json(json).unwrap()).unwrap();
+
+        assert!(pth.join("cat.svg").exists());
+        assert!(!pth.join("dog.svg").exists());
+        fs::remove_dir_all(&pth).unwrap();
+    }
+
+    #[test]
+    fn stash_round_trip() {
+        let pth = git::temp_repo("stash");
+        let sb3 = pth.with_extension("sb3");
+        let clean = r#"{"targets":[{"isStage":true,"name":"Stage"}]}"#;
+        let edited =
+            r#"{"targets":[{"isStage":true,"name":"Stage"},{"isStage":false,"name":"Cat"}]}"#;
+        fs::write(pth.join(".gitignore"), "project.old.json\n").unwrap();
+        fs::write(pth.join("project.json"), clean).unwrap();
+        git::run(vec!["add", "."], Some(&pth)).status().unwrap();
+        git::run(vec!["commit", "-q", "-m", "init"], Some(&pth))
+            .status()
+            .unwrap();
+
+        let stash = save_stash(&pth, sb3.to_str().unwrap(), None).unwrap();
+        assert_eq!(stash, json!({ "status": "nothing to stash" }));
+
+        fs::write(pth.join("project.json"), edited).unwrap();
+        let stash = save_stash(&pth, sb3.to_str().unwrap(), Some("add cat")).unwrap();
+        assert_eq!(stash, json!({ "status": "success" }));
+        assert_eq!(fs::read_to_string(pth.join("project.json")).unwrap(), clean);
+        assert_eq!(
+            fs::read_to_string(pth.join("project.old.json")).unwrap(),
+            clean
+        );
+        assert!(sb3.exists());
+
+        assert_eq!(
+            apply_stash(&pth, sb3.to_str().unwrap(), 1).unwrap(),
+            json!({ "status": "no such stash" })
+        );
+        assert_eq!(
+            drop_stash(&pth, 1).unwrap(),
+            json!({ "success": false, "status": "no such stash" })
+        );
+        assert_eq!(git::stash_list(&pth).unwrap().len(), 1);
+
+        let apply = apply_stash(&pth, sb3.to_str().unwrap(), 0).unwrap();
+        assert_eq!(apply, json!({ "status": "success" }));
+        assert_eq!(
+            fs::read_to_string(pth.join("project.json")).unwrap(),
+            edited
+        );
+
+        // the changes are back, so applying again would overwrite them
+        let apply = apply_stash(&pth, sb3.to_str().unwrap(), 0).unwrap();
+        assert_eq!(apply["status"], "uncommitted changes");
+
+        assert_eq!(drop_stash(&pth, 0).unwrap(), json!({ "success": true }));
+        assert!(git::stash_list(&pth).unwrap().is_empty());
+
+        fs::remove_dir_all(&pth).unwrap();
+        fs::remove_file(&sb3).unwrap();
+    }
+}
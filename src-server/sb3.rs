@@ -1,44 +1,859 @@
-use serde::Deserialize;
+use std::collections::HashMap;
 
-#[derive(Debug, Deserialize)]
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use serde::{de, ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Number, Value};
+
+/// Represents a whole project.json
+///
+/// Every struct in the model keeps fields it doesn't know about in `extra`,
+/// so a project can be deserialized and serialized again without losing anything
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ProjectData {
+    pub targets: Vec<Target>,
+    /// `None` if the key is missing, so it isn't added when the project is written back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitors: Option<Vec<Monitor>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<String>>,
+    /// Custom extension URLs by extension ID, only written by TurboWarp
+    #[serde(
+        rename = "extensionURLs",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub extension_urls: Option<IndexMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Meta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub semver: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vm: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Deserialize a key that may be `null` so it can be told apart from a missing key
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error> {
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Represents a sprite or the stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CostumeOrSound {
+pub struct Target {
+    pub is_stage: bool,
+    pub name: String,
+    #[serde(default)]
+    pub variables: IndexMap<String, Variable>,
+    #[serde(default)]
+    pub lists: IndexMap<String, List>,
+    #[serde(default)]
+    pub broadcasts: IndexMap<String, String>,
+    #[serde(default)]
+    pub blocks: IndexMap<String, Block>,
+    #[serde(default)]
+    pub comments: IndexMap<String, Comment>,
+    #[serde(default)]
+    pub current_costume: usize,
+    #[serde(default)]
+    pub costumes: Vec<Costume>,
+    #[serde(default)]
+    pub sounds: Vec<Sound>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer_order: Option<i64>,
+
+    // stage-only properties
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tempo: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_transparency: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_state: Option<String>,
+    /// Outer `None` if the key is missing, inner `None` if it's `null`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "present"
+    )]
+    pub text_to_speech_language: Option<Option<String>>,
+
+    // sprite-only properties
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draggable: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation_style: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Target {
+    /// The name of the target, with the stage marked as `(stage)`
+    pub fn display_name(&self) -> String {
+        if self.is_stage {
+            format!("{} (stage)", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// Look up a block by its ID, failing if a script refers to a block that doesn't exist
+    pub fn block(&self, id: &str) -> Result<&Block> {
+        self.blocks.get(id).ok_or(anyhow!(
+            "{} refers to missing block {id}",
+            self.display_name()
+        ))
+    }
+}
+
+/// Represents a costume, with its keys in the order Scratch writes them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Costume {
+    pub asset_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitmap_resolution: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub md5ext: Option<String>,
     pub data_format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation_center_x: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation_center_y: Option<Number>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Represents a sound, with its keys in the order Scratch writes them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sound {
     pub asset_id: String,
+    pub name: String,
+    pub data_format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_count: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub md5ext: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct Target {
-    pub costumes: Vec<CostumeOrSound>,
-    pub sounds: Vec<CostumeOrSound>,
+/// Shared accessors for costumes and sounds
+pub trait Asset {
+    fn name(&self) -> &str;
+    fn asset_id(&self) -> &str;
+    fn data_format(&self) -> &str;
+    fn md5ext(&self) -> Option<&str>;
+
+    /// The file name of the asset inside the project directory
+    fn file_name(&self) -> String {
+        self.md5ext().map(|md5| md5.to_string()).unwrap_or(format!(
+            "{}.{}",
+            self.asset_id(),
+            self.data_format()
+        ))
+    }
+
+    /// Readable settings stored alongside the asset's file, like a costume's rotation center
+    fn metadata(&self) -> Vec<(&'static str, String)>;
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ProjectData {
-    pub targets: Vec<Target>,
+/// Text of an optional number, or `none` if it's missing
+pub fn number_text(number: &Option<Number>) -> String {
+    number
+        .as_ref()
+        .map(|n| n.to_string())
+        .unwrap_or("none".into())
+}
+
+impl Asset for Costume {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn asset_id(&self) -> &str {
+        &self.asset_id
+    }
+    fn data_format(&self) -> &str {
+        &self.data_format
+    }
+    fn md5ext(&self) -> Option<&str> {
+        self.md5ext.as_deref()
+    }
+    fn metadata(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "rotation center",
+                format!(
+                    "{},{}",
+                    number_text(&self.rotation_center_x),
+                    number_text(&self.rotation_center_y)
+                ),
+            ),
+            ("bitmap resolution", number_text(&self.bitmap_resolution)),
+        ]
+    }
+}
+
+impl Asset for Sound {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn asset_id(&self) -> &str {
+        &self.asset_id
+    }
+    fn data_format(&self) -> &str {
+        &self.data_format
+    }
+    fn md5ext(&self) -> Option<&str> {
+        self.md5ext.as_deref()
+    }
+    fn metadata(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("rate", number_text(&self.rate)),
+            ("sample count", number_text(&self.sample_count)),
+        ]
+    }
+}
+
+/// Represents a variable, stored as `[name, value]` or `[name, value, true]` for cloud variables
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub value: Value,
+    pub is_cloud: bool,
+}
+
+impl Serialize for Variable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        seq.serialize_element(&self.name)?;
+        seq.serialize_element(&self.value)?;
+        if self.is_cloud {
+            seq.serialize_element(&true)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Variable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = Vec::<Value>::deserialize(deserializer)?;
+        let name = items
+            .first()
+            .and_then(|n| n.as_str())
+            .ok_or(de::Error::custom("variable is missing a name"))?;
+        Ok(Variable {
+            name: name.into(),
+            value: items.get(1).cloned().unwrap_or_default(),
+            is_cloud: items.get(2).and_then(|c| c.as_bool()).unwrap_or_default(),
+        })
+    }
+}
+
+/// Represents a list, stored as `[name, [items...]]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct List {
+    pub name: String,
+    pub items: Vec<Value>,
+}
+
+impl Serialize for List {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (&self.name, &self.items).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for List {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (name, items) = <(String, Vec<Value>)>::deserialize(deserializer)?;
+        Ok(List { name, items })
+    }
+}
+
+/// Represents a block, which is either a regular block or a variable or list reporter
+/// dropped directly into the workspace
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Normal(Box<NormalBlock>),
+    Primitive(Primitive),
+}
+
+impl Block {
+    /// Return the block if it's a regular block
+    pub fn normal(&self) -> Option<&NormalBlock> {
+        match self {
+            Block::Normal(block) => Some(block.as_ref()),
+            Block::Primitive(_) => None,
+        }
+    }
+
+    /// Whether the block starts a script
+    pub fn is_top_level(&self) -> bool {
+        match self {
+            Block::Normal(block) => block.top_level || block.parent.is_none(),
+            Block::Primitive(_) => true,
+        }
+    }
+}
+
+impl Serialize for Block {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Block::Normal(block) => block.serialize(serializer),
+            Block::Primitive(primitive) => primitive.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Block {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        if value.is_array() {
+            serde_json::from_value(value)
+                .map(Block::Primitive)
+                .map_err(de::Error::custom)
+        } else {
+            serde_json::from_value(value)
+                .map(|block| Block::Normal(Box::new(block)))
+                .map_err(de::Error::custom)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalBlock {
+    pub opcode: String,
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub inputs: IndexMap<String, Input>,
+    #[serde(default)]
+    pub fields: IndexMap<String, Field>,
+    #[serde(default)]
+    pub shadow: bool,
+    #[serde(default)]
+    pub top_level: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mutation: Option<Mutation>,
+    /// ID of the comment attached to this block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Represents the extra state of custom blocks and a few others
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mutation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proccode: Option<String>,
+    /// JSON-encoded array of argument IDs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub argumentids: Option<String>,
+    /// JSON-encoded array of argument names, only on prototypes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub argumentnames: Option<String>,
+    /// JSON-encoded array of argument defaults, only on prototypes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub argumentdefaults: Option<String>,
+    /// Whether the custom block runs without screen refresh, either a boolean or `"true"`/`"false"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warp: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Mutation {
+    fn decode(list: &Option<String>) -> Vec<String> {
+        list.as_deref()
+            .and_then(|l| serde_json::from_str(l).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn argument_ids(&self) -> Vec<String> {
+        Mutation::decode(&self.argumentids)
+    }
+
+    pub fn argument_names(&self) -> Vec<String> {
+        Mutation::decode(&self.argumentnames)
+    }
+
+    pub fn warp(&self) -> bool {
+        match &self.warp {
+            Some(Value::Bool(warp)) => *warp,
+            Some(Value::String(warp)) => warp == "true",
+            _ => false,
+        }
+    }
+}
+
+/// Represents where a block input's value comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputValue {
+    Block(String),
+    Primitive(Primitive),
+}
+
+impl InputValue {
+    fn from_value(value: Value) -> Result<Option<Self>, String> {
+        match value {
+            Value::Null => Ok(None),
+            Value::String(id) => Ok(Some(InputValue::Block(id))),
+            value @ Value::Array(_) => serde_json::from_value(value)
+                .map(|p| Some(InputValue::Primitive(p)))
+                .map_err(|e| e.to_string()),
+            value => Err(format!("unexpected input value {value}")),
+        }
+    }
+}
+
+impl Serialize for InputValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            InputValue::Block(id) => id.serialize(serializer),
+            InputValue::Primitive(primitive) => primitive.serialize(serializer),
+        }
+    }
+}
+
+/// Represents a block input, stored as `[1, shadow]`, `[2, block]` or `[3, block, shadow]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Input {
+    /// The block or value placed in the input
+    pub value: Option<InputValue>,
+    /// The shadow underneath the block, if a block is obscuring one
+    pub shadow: Option<InputValue>,
+    /// Whether `value` is the input's own shadow rather than a block placed in it
+    pub is_shadow: bool,
+    /// Whether the input was stored as `[3, block, null]`, which has room for a shadow but none
+    /// in it, so it's written back the same way
+    obscures: bool,
+}
+
+impl Serialize for Input {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        if self.is_shadow {
+            seq.serialize_element(&1)?;
+            seq.serialize_element(&self.value)?;
+        } else if self.shadow.is_none() && !self.obscures {
+            seq.serialize_element(&2)?;
+            seq.serialize_element(&self.value)?;
+        } else {
+            seq.serialize_element(&3)?;
+            seq.serialize_element(&self.value)?;
+            seq.serialize_element(&self.shadow)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Input {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut items = Vec::<Value>::deserialize(deserializer)?.into_iter();
+        let kind = items.next().and_then(|k| k.as_u64());
+        let value =
+            InputValue::from_value(items.next().unwrap_or_default()).map_err(de::Error::custom)?;
+        let shadow =
+            InputValue::from_value(items.next().unwrap_or_default()).map_err(de::Error::custom)?;
+
+        match kind {
+            Some(1) => Ok(Input {
+                value,
+                shadow: None,
+                is_shadow: true,
+                obscures: false,
+            }),
+            Some(kind @ (2 | 3)) => Ok(Input {
+                value,
+                shadow,
+                is_shadow: false,
+                obscures: kind == 3,
+            }),
+            _ => Err(de::Error::custom("unknown input type")),
+        }
+    }
+}
+
+/// Represents a block field, stored as `[value]` or `[value, id]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub value: Value,
+    /// ID of the variable, list or broadcast the field refers to
+    pub id: Option<String>,
+    has_id: bool,
+}
+
+impl Field {
+    pub fn new(value: Value, id: Option<String>) -> Self {
+        Field {
+            value,
+            id,
+            has_id: true,
+        }
+    }
+
+    /// The field's value as text
+    pub fn text(&self) -> String {
+        match &self.value {
+            Value::String(s) => s.clone(),
+            value => value.to_string(),
+        }
+    }
+}
+
+impl Serialize for Field {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        seq.serialize_element(&self.value)?;
+        if self.has_id || self.id.is_some() {
+            seq.serialize_element(&self.id)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = Vec::<Value>::deserialize(deserializer)?;
+        Ok(Field {
+            value: items.first().cloned().unwrap_or_default(),
+            id: items.get(1).and_then(|i| i.as_str()).map(|i| i.to_string()),
+            has_id: items.len() > 1,
+        })
+    }
+}
+
+/// Represents a compressed value or reporter, stored as `[type, value, ...]`
+///
+/// Elements after the ones Scratch uses are kept in `rest` so they're written back unchanged
+#[derive(Debug, Clone, PartialEq)]
+pub enum Primitive {
+    /// Number, positive number, whole number, integer and angle inputs (types 4 to 8)
+    Number(u8, Value, Vec<Value>),
+    Color(Value, Vec<Value>),
+    Text(Value, Vec<Value>),
+    Broadcast {
+        name: String,
+        id: String,
+        rest: Vec<Value>,
+    },
+    /// A variable reporter, with a position if it's placed directly in the workspace
+    Variable {
+        name: String,
+        id: String,
+        position: Option<(Number, Number)>,
+        rest: Vec<Value>,
+    },
+    List {
+        name: String,
+        id: String,
+        position: Option<(Number, Number)>,
+        rest: Vec<Value>,
+    },
+}
+
+impl Primitive {
+    /// The value a primitive holds, or the name of the variable, list or broadcast it refers to
+    pub fn text(&self) -> String {
+        let text = |value: &Value| match value {
+            Value::String(s) => s.clone(),
+            value => value.to_string(),
+        };
+        match self {
+            Primitive::Number(_, value, _)
+            | Primitive::Color(value, _)
+            | Primitive::Text(value, _) => text(value),
+            Primitive::Broadcast { name, .. }
+            | Primitive::Variable { name, .. }
+            | Primitive::List { name, .. } => name.clone(),
+        }
+    }
+}
+
+impl Serialize for Primitive {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (mut items, rest): (Vec<Value>, _) = match self {
+            Primitive::Number(kind, value, rest) => (vec![(*kind).into(), value.clone()], rest),
+            Primitive::Color(value, rest) => (vec![9.into(), value.clone()], rest),
+            Primitive::Text(value, rest) => (vec![10.into(), value.clone()], rest),
+            Primitive::Broadcast { name, id, rest } => (
+                vec![11.into(), name.clone().into(), id.clone().into()],
+                rest,
+            ),
+            Primitive::Variable {
+                name,
+                id,
+                position,
+                rest,
+            }
+            | Primitive::List {
+                name,
+                id,
+                position,
+                rest,
+            } => {
+                let kind = if matches!(self, Primitive::Variable { .. }) {
+                    12
+                } else {
+                    13
+                };
+                let mut items = vec![kind.into(), name.clone().into(), id.clone().into()];
+                if let Some((x, y)) = position {
+                    items.extend([Value::Number(x.clone()), Value::Number(y.clone())]);
+                }
+                (items, rest)
+            }
+        };
+        items.extend(rest.iter().cloned());
+        items.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Primitive {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = Vec::<Value>::deserialize(deserializer)?;
+        let kind = items
+            .first()
+            .and_then(|k| k.as_u64())
+            .ok_or(de::Error::custom("primitive is missing its type"))?;
+        let value = items.get(1).cloned().unwrap_or_default();
+        let string = |i: usize| {
+            items
+                .get(i)
+                .and_then(|s| s.as_str())
+                .map(|s| s.to_string())
+                .ok_or(de::Error::custom(format!(
+                    "primitive of type {kind} is malformed"
+                )))
+        };
+        let position = match (items.get(3), items.get(4)) {
+            (Some(Value::Number(x)), Some(Value::Number(y))) => Some((x.clone(), y.clone())),
+            _ => None,
+        };
+        let rest = |from: usize| items.iter().skip(from).cloned().collect::<Vec<_>>();
+        let reporter_rest = rest(if position.is_some() { 5 } else { 3 });
+
+        Ok(match kind {
+            4..=8 => Primitive::Number(kind as u8, value, rest(2)),
+            9 => Primitive::Color(value, rest(2)),
+            10 => Primitive::Text(value, rest(2)),
+            11 => Primitive::Broadcast {
+                name: string(1)?,
+                id: string(2)?,
+                rest: rest(3),
+            },
+            12 => Primitive::Variable {
+                name: string(1)?,
+                id: string(2)?,
+                position,
+                rest: reporter_rest,
+            },
+            13 => Primitive::List {
+                name: string(1)?,
+                id: string(2)?,
+                position,
+                rest: reporter_rest,
+            },
+            _ => return Err(de::Error::custom(format!("unknown primitive type {kind}"))),
+        })
+    }
+}
+
+/// Represents a workspace comment, either floating or attached to a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    #[serde(default)]
+    pub block_id: Option<String>,
+    #[serde(default)]
+    pub x: Option<Number>,
+    #[serde(default)]
+    pub y: Option<Number>,
+    #[serde(default)]
+    pub width: Option<Number>,
+    #[serde(default)]
+    pub height: Option<Number>,
+    #[serde(default)]
+    pub minimized: bool,
+    #[serde(default)]
+    pub text: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Represents a variable or list monitor shown on the stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Monitor {
+    pub id: String,
+    #[serde(default)]
+    pub mode: String,
+    #[serde(default)]
+    pub opcode: String,
+    #[serde(default)]
+    pub params: IndexMap<String, Value>,
+    #[serde(default)]
+    pub sprite_name: Option<String>,
+    #[serde(default)]
+    pub value: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<Number>,
+    #[serde(default)]
+    pub visible: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slider_min: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slider_max: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_discrete: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Represents a costume or sound that uses a certain asset file
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct AssetOwner {
+    pub sprite: String,
+    pub name: String,
+    pub kind: &'static str,
+    pub on_stage: bool,
+}
+
+impl ProjectData {
+    /// Parse a project.json, failing on anything that isn't a valid Scratch 3 project
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Monitors shown on the stage, empty if the project has none
+    pub fn monitors(&self) -> &[Monitor] {
+        self.monitors.as_deref().unwrap_or_default()
+    }
+
+    /// IDs of the extensions the project uses, empty if it uses none
+    pub fn extensions(&self) -> &[String] {
+        self.extensions.as_deref().unwrap_or_default()
+    }
+
+    /// The stage target, which holds global variables, lists and broadcasts
+    pub fn stage(&self) -> Option<&Target> {
+        self.targets.iter().find(|t| t.is_stage)
+    }
+
+    /// Map each asset file name to the costumes and sounds that use it
+    pub fn asset_owners(&self) -> HashMap<String, Vec<AssetOwner>> {
+        let mut owners: HashMap<String, Vec<AssetOwner>> = HashMap::new();
+        for target in &self.targets {
+            let costumes = target.costumes.iter().map(|c| (c as &dyn Asset, "costume"));
+            let sounds = target.sounds.iter().map(|s| (s as &dyn Asset, "sound"));
+            for (asset, kind) in costumes.chain(sounds) {
+                owners
+                    .entry(asset.file_name())
+                    .or_default()
+                    .push(AssetOwner {
+                        sprite: target.name.clone(),
+                        name: asset.name().into(),
+                        kind,
+                        on_stage: target.is_stage,
+                    });
+            }
+        }
+        owners
+    }
 }
 
 pub fn get_assets(project: ProjectData) -> Vec<String> {
     project
         .targets
         .iter()
-        .map(|t| {
-            let sounds = t.sounds.iter().map(|s| {
-                s.md5ext
-                    .clone()
-                    .unwrap_or(format!("{}.{}", s.asset_id, s.data_format))
-                    .to_string()
-            });
-            let costumes = t.costumes.iter().map(|s| {
-                s.md5ext
-                    .clone()
-                    .unwrap_or(format!("{}.{}", s.asset_id, s.data_format))
-                    .to_string()
-            });
+        .flat_map(|t| {
+            let sounds = t.sounds.iter().map(|s| s.file_name());
+            let costumes = t.costumes.iter().map(|c| c.file_name());
 
             sounds.chain(costumes).collect::<Vec<String>>()
         })
-        .flatten()
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A project as saved by Scratch 3, with a monitor, a custom block and a workspace comment
+    const SCRATCH_PROJECT: &str = r#"{"targets":[{"isStage":true,"name":"Stage","variables":{"`jEk@4|i[#Fk?(8x)AV.-my variable":["my variable",0],"cloud":["☁ high score",10,true]},"lists":{"list":["items",["a","b",3]]},"broadcasts":{"broadcast":"game over"},"blocks":{},"comments":{},"currentCostume":0,"costumes":[{"assetId":"cd21514d0531fdffb22204e0ec5ed84a","name":"backdrop1","md5ext":"cd21514d0531fdffb22204e0ec5ed84a.svg","dataFormat":"svg","rotationCenterX":240,"rotationCenterY":180}],"sounds":[{"assetId":"83a9787d4cb6f3b7632b4ddfebf74367","name":"pop","dataFormat":"wav","format":"","rate":48000,"sampleCount":1123,"md5ext":"83a9787d4cb6f3b7632b4ddfebf74367.wav"}],"volume":100,"layerOrder":0,"tempo":60,"videoTransparency":50,"videoState":"on","textToSpeechLanguage":null},{"isStage":false,"name":"Sprite1","variables":{},"lists":{},"broadcasts":{},"blocks":{"flag":{"opcode":"event_whenflagclicked","next":"if","parent":null,"inputs":{},"fields":{},"shadow":false,"topLevel":true,"x":48,"y":64},"if":{"opcode":"control_if_else","next":"call","parent":"flag","inputs":{"CONDITION":[2,"gt"],"SUBSTACK":[2,"move"],"SUBSTACK2":[2,"say"]},"fields":{},"shadow":false,"topLevel":false},"gt":{"opcode":"operator_gt","next":null,"parent":"if","inputs":{"OPERAND1":[3,[12,"my variable","`jEk@4|i[#Fk?(8x)AV.-my variable"],[10,""]],"OPERAND2":[1,[10,"50"]]},"fields":{},"shadow":false,"topLevel":false},"move":{"opcode":"motion_movesteps","next":null,"parent":"if","inputs":{"STEPS":[1,[4,"10"]]},"fields":{},"shadow":false,"topLevel":false},"say":{"opcode":"looks_sayforsecs","next":null,"parent":"if","inputs":{"MESSAGE":[1,[10,"Hello!"]],"SECS":[1,[4,"2"]]},"fields":{},"shadow":false,"topLevel":false,"comment":"note"},"call":{"opcode":"procedures_call","next":"send","parent":"if","inputs":{"input0":[1,[10,"3"]]},"fields":{},"shadow":false,"topLevel":false,"mutation":{"tagName":"mutation","children":[],"proccode":"jump %s","argumentids":"[\"input0\"]","warp":"false"}},"send":{"opcode":"event_broadcast","next":null,"parent":"call","inputs":{"BROADCAST_INPUT":[1,[11,"game over","broadcast"]]},"fields":{},"shadow":false,"topLevel":false},"define":{"opcode":"procedures_definition","next":null,"parent":null,"inputs":{"custom_block":[1,"prototype"]},"fields":{},"shadow":false,"topLevel":true,"x":400,"y":64},"prototype":{"opcode":"procedures_prototype","next":null,"parent":"define","inputs":{"input0":[1,"argument"]},"fields":{},"shadow":true,"topLevel":false,"mutation":{"tagName":"mutation","children":[],"proccode":"jump %s","argumentids":"[\"input0\"]","argumentnames":"[\"height\"]","argumentdefaults":"[\"\"]","warp":"false"}},"argument":{"opcode":"argument_reporter_string_number","next":null,"parent":"prototype","inputs":{},"fields":{"VALUE":["height",null]},"shadow":true,"topLevel":false},"loose":[12,"my variable","`jEk@4|i[#Fk?(8x)AV.-my variable",400,300]},"comments":{"note":{"blockId":"say","x":300,"y":120,"width":200,"height":200,"minimized":false,"text":"greets the player"}},"currentCostume":0,"costumes":[{"assetId":"bcf454acf82e4504149f7ffe07081dbc","name":"costume1","bitmapResolution":1,"md5ext":"bcf454acf82e4504149f7ffe07081dbc.svg","dataFormat":"svg","rotationCenterX":48,"rotationCenterY":50}],"sounds":[],"volume":100,"layerOrder":1,"visible":true,"x":0,"y":0,"size":100,"direction":90,"draggable":false,"rotationStyle":"all around"}],"monitors":[{"id":"`jEk@4|i[#Fk?(8x)AV.-my variable","mode":"default","opcode":"data_variable","params":{"VARIABLE":"my variable"},"spriteName":null,"value":0,"width":0,"height":0,"x":5,"y":5,"visible":true,"sliderMin":0,"sliderMax":100,"isDiscrete":true}],"extensions":["pen"],"meta":{"semver":"3.0.0","vm":"5.0.40","agent":"Mozilla/5.0"}}"#;
+
+    /// A hand-written project without monitors, extensions or metadata, with an input stored as
+    /// `[3, block, null]` and primitives carrying elements Scratch doesn't use
+    const SPARSE_PROJECT: &str = r#"{"targets":[{"isStage":true,"name":"Stage","variables":{},"lists":{},"broadcasts":{},"blocks":{"say":{"opcode":"looks_say","next":null,"parent":null,"inputs":{"MESSAGE":[3,"answer",null]},"fields":{},"shadow":false,"topLevel":true,"x":0,"y":0},"answer":{"opcode":"sensing_answer","next":null,"parent":"say","inputs":{},"fields":{},"shadow":false,"topLevel":false},"wait":{"opcode":"control_wait","next":null,"parent":null,"inputs":{"DURATION":[1,[5,"1","extra"]]},"fields":{},"shadow":false,"topLevel":true,"x":0,"y":100},"loose":[12,"v","id",10,20,"note"]},"comments":{},"currentCostume":0,"costumes":[{"assetId":"cd21514d0531fdffb22204e0ec5ed84a","name":"backdrop1","md5ext":"cd21514d0531fdffb22204e0ec5ed84a.svg","dataFormat":"svg","rotationCenterX":240,"rotationCenterY":180}],"sounds":[]}],"meta":{"vm":"0.2.0"}}"#;
+
+    fn round_trip(json: &str) -> String {
+        serde_json::to_string(&ProjectData::from_json(json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn round_trip_scratch_project() {
+        assert_eq!(round_trip(SCRATCH_PROJECT), SCRATCH_PROJECT);
+    }
+
+    #[test]
+    fn round_trip_sparse_project() {
+        assert_eq!(round_trip(SPARSE_PROJECT), SPARSE_PROJECT);
+    }
+
+    #[test]
+    fn missing_keys_read_as_empty() {
+        let project = ProjectData::from_json(SPARSE_PROJECT).unwrap();
+        assert!(project.monitors().is_empty());
+        assert!(project.extensions().is_empty());
+        assert_eq!(project.meta.unwrap().semver, None);
+    }
+}